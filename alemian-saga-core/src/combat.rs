@@ -0,0 +1,250 @@
+use crate::*;
+use numeric_types::*;
+
+// Damage dealt by a critical hit is multiplied by this amount
+pub const CRITICAL_MULTIPLIER: i32 = 3;
+
+// Chances are expressed as percentages, so they are clamped to this range
+const MIN_CHANCE: AccuracyPoints = accuracy_pts(0);
+const MAX_CHANCE: AccuracyPoints = accuracy_pts(100);
+
+// Combat-relevant statistics of a unit, including the bonuses of the tile it stands on
+#[derive(Clone, Copy)]
+pub struct Combatant {
+    pub hp: HitPoints,
+    pub attack: HitPoints,
+    pub defense: HitPoints,
+    pub accuracy: AccuracyPoints,
+    pub evade: AccuracyPoints,
+    pub critical: AccuracyPoints,
+    pub tile_defense: HitPoints,
+    pub tile_evade: AccuracyPoints,
+}
+
+impl Combatant {
+    pub fn new(unit: &serialization::Unit, tile: &serialization::TileType) -> Self {
+        Self {
+            hp: unit.hp,
+            attack: unit.attack,
+            defense: unit.defense,
+            accuracy: unit.accuracy,
+            evade: unit.evade,
+            critical: unit.critical,
+            tile_defense: tile.defense,
+            tile_evade: tile.evade,
+        }
+    }
+}
+
+// Expected effect of one unit striking another
+#[derive(Clone, Copy)]
+pub struct Strike {
+    pub damage: HitPoints,
+    pub hit_chance: AccuracyPoints,
+    pub critical_chance: AccuracyPoints,
+}
+
+// Expected effects of an attack and the counter-attack that follows it
+#[derive(Clone, Copy)]
+pub struct Forecast {
+    pub attack: Strike,
+    pub counter: Strike,
+}
+
+// What actually happened when one unit struck another
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StrikeResult {
+    Miss,
+    Hit(HitPoints),
+    Critical(HitPoints),
+}
+
+// What actually happened during an attack
+#[derive(Clone, Copy)]
+pub struct Outcome {
+    pub attack: StrikeResult,
+    // None if the defender was defeated before it could counter-attack
+    pub counter: Option<StrikeResult>,
+    pub attacker_hp: HitPoints,
+    pub defender_hp: HitPoints,
+}
+
+fn clamp_chance(chance: AccuracyPoints) -> AccuracyPoints {
+    if chance < MIN_CHANCE {
+        MIN_CHANCE
+    } else if chance > MAX_CHANCE {
+        MAX_CHANCE
+    } else {
+        chance
+    }
+}
+
+// Computes the damage and chances of one unit striking another
+pub fn strike(attacker: &Combatant, defender: &Combatant) -> Strike {
+    let damage = attacker.attack - defender.defense - defender.tile_defense;
+    Strike {
+        damage: if damage > hp(0) { damage } else { hp(0) },
+        hit_chance: clamp_chance(attacker.accuracy - defender.evade - defender.tile_evade),
+        critical_chance: clamp_chance(attacker.critical),
+    }
+}
+
+// Computes the expected effects of an attack without resolving it
+pub fn forecast(attacker: &Combatant, defender: &Combatant) -> Forecast {
+    Forecast {
+        attack: strike(attacker, defender),
+        counter: strike(defender, attacker),
+    }
+}
+
+// Determines the result of a strike from two rolls in the range [0, 100)
+pub fn resolve_strike(
+    strike: &Strike,
+    hit_roll: AccuracyPoints,
+    critical_roll: AccuracyPoints,
+) -> StrikeResult {
+    if hit_roll >= strike.hit_chance {
+        StrikeResult::Miss
+    } else if critical_roll < strike.critical_chance {
+        StrikeResult::Critical(strike.damage * CRITICAL_MULTIPLIER)
+    } else {
+        StrikeResult::Hit(strike.damage)
+    }
+}
+
+fn apply_strike(target_hp: HitPoints, result: StrikeResult) -> HitPoints {
+    let remaining = match result {
+        StrikeResult::Miss => target_hp,
+        StrikeResult::Hit(damage) | StrikeResult::Critical(damage) => target_hp - damage,
+    };
+    if remaining > hp(0) {
+        remaining
+    } else {
+        hp(0)
+    }
+}
+
+// Resolves an attack and the counter-attack that follows it
+// roll is called for every random decision and must yield values in the range [0, 100)
+pub fn resolve<R: FnMut() -> AccuracyPoints>(
+    attacker: &Combatant,
+    defender: &Combatant,
+    mut roll: R,
+) -> Outcome {
+    let expected = forecast(attacker, defender);
+    let attack = resolve_strike(&expected.attack, roll(), roll());
+    let defender_hp = apply_strike(defender.hp, attack);
+    let counter = if defender_hp > hp(0) {
+        Some(resolve_strike(&expected.counter, roll(), roll()))
+    } else {
+        None
+    };
+    let attacker_hp = match counter {
+        Some(result) => apply_strike(attacker.hp, result),
+        None => attacker.hp,
+    };
+    Outcome {
+        attack,
+        counter,
+        attacker_hp,
+        defender_hp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combatant(hp_value: i32, attack: i32, defense: i32) -> Combatant {
+        Combatant {
+            hp: hp(hp_value),
+            attack: hp(attack),
+            defense: hp(defense),
+            accuracy: accuracy_pts(90),
+            evade: accuracy_pts(10),
+            critical: accuracy_pts(5),
+            tile_defense: hp(1),
+            tile_evade: accuracy_pts(10),
+        }
+    }
+
+    // Makes a roll function that yields the given rolls in order and fails if more are needed
+    fn rolls(values: &[i32]) -> impl FnMut() -> AccuracyPoints + '_ {
+        let mut values = values.iter();
+        move || accuracy_pts(*values.next().expect("rolled more times than expected"))
+    }
+
+    #[test]
+    fn strike_subtracts_defense_and_evade() {
+        let expected = strike(&combatant(20, 10, 3), &combatant(20, 10, 3));
+        assert_eq!(expected.damage, hp(6));
+        assert_eq!(expected.hit_chance, accuracy_pts(70));
+        assert_eq!(expected.critical_chance, accuracy_pts(5));
+    }
+
+    #[test]
+    fn strike_clamps_damage_and_chances() {
+        let mut attacker = combatant(20, 2, 3);
+        attacker.accuracy = accuracy_pts(150);
+        attacker.critical = accuracy_pts(-5);
+        let expected = strike(&attacker, &combatant(20, 10, 3));
+        assert_eq!(expected.damage, hp(0));
+        assert_eq!(expected.hit_chance, accuracy_pts(100));
+        assert_eq!(expected.critical_chance, accuracy_pts(0));
+    }
+
+    #[test]
+    fn rolls_below_the_hit_chance_hit() {
+        let expected = strike(&combatant(20, 10, 3), &combatant(20, 10, 3));
+        let hit = resolve_strike(&expected, accuracy_pts(69), accuracy_pts(5));
+        assert!(hit == StrikeResult::Hit(hp(6)));
+    }
+
+    #[test]
+    fn rolls_at_or_above_the_hit_chance_miss() {
+        let expected = strike(&combatant(20, 10, 3), &combatant(20, 10, 3));
+        let miss = resolve_strike(&expected, accuracy_pts(70), accuracy_pts(0));
+        assert!(miss == StrikeResult::Miss);
+    }
+
+    #[test]
+    fn critical_hits_multiply_damage() {
+        let expected = strike(&combatant(20, 10, 3), &combatant(20, 10, 3));
+        let critical = resolve_strike(&expected, accuracy_pts(0), accuracy_pts(4));
+        assert!(critical == StrikeResult::Critical(hp(6 * CRITICAL_MULTIPLIER)));
+    }
+
+    #[test]
+    fn surviving_defenders_counter_attack() {
+        let attacker = combatant(20, 10, 3);
+        let defender = combatant(20, 8, 2);
+        let outcome = resolve(&attacker, &defender, rolls(&[0, 50, 0, 50]));
+        assert!(outcome.attack == StrikeResult::Hit(hp(7)));
+        assert!(outcome.counter == Some(StrikeResult::Hit(hp(4))));
+        assert_eq!(outcome.defender_hp, hp(13));
+        assert_eq!(outcome.attacker_hp, hp(16));
+    }
+
+    #[test]
+    fn missed_counter_attacks_leave_the_attacker_unharmed() {
+        let attacker = combatant(20, 10, 3);
+        let defender = combatant(20, 8, 2);
+        let outcome = resolve(&attacker, &defender, rolls(&[99, 0, 99, 0]));
+        assert!(outcome.attack == StrikeResult::Miss);
+        assert!(outcome.counter == Some(StrikeResult::Miss));
+        assert_eq!(outcome.defender_hp, hp(20));
+        assert_eq!(outcome.attacker_hp, hp(20));
+    }
+
+    #[test]
+    fn lethal_first_strikes_skip_the_counter_attack() {
+        let attacker = combatant(20, 10, 3);
+        let defender = combatant(5, 50, 2);
+        // Only the attacker's two rolls are available, so rolling for a counter would panic
+        let outcome = resolve(&attacker, &defender, rolls(&[0, 50]));
+        assert!(outcome.attack == StrikeResult::Hit(hp(7)));
+        assert!(outcome.counter.is_none());
+        assert_eq!(outcome.defender_hp, hp(0));
+        assert_eq!(outcome.attacker_hp, hp(20));
+    }
+}
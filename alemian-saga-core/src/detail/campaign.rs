@@ -0,0 +1,197 @@
+use crate::*;
+use constants::*;
+use detail::*;
+
+// Campaign used when no campaign file is available, which only has the default map
+pub fn default_campaign() -> serialization::Campaign<'static> {
+    serialization::Campaign {
+        chapters: vec![serialization::Chapter {
+            map: DEFAULT_MAP,
+            name: DEFAULT_MAP,
+        }],
+    }
+}
+
+// Gets the index of the chapter that is played on a map
+pub fn find_chapter(campaign: &serialization::Campaign, map: &str) -> Option<usize> {
+    campaign.chapters.iter().position(|c| c.map == map)
+}
+
+// Gets the player units for a chapter, replacing the units listed in the map with the ones that
+// were carried over from earlier chapters
+// Carried units keep their stats but start where the map places them, with full health
+// Fallen units are left out unless they are required, since the map can't be won without them
+pub fn deploy<'a>(
+    map_units: &[serialization::Unit<'a>],
+    roster: &serialization::Roster<'a>,
+    required: &[&str],
+) -> Vec<serialization::Unit<'a>> {
+    map_units
+        .iter()
+        .filter(|u| !roster.fallen.contains(&u.name) || required.contains(&u.name))
+        .map(|u| match roster.units.iter().find(|r| r.name == u.name) {
+            Some(carried) => serialization::Unit {
+                hp: carried.max_hp,
+                remaining_move: carried.movement,
+                position: u.position,
+                ..carried.clone()
+            },
+            None => u.clone(),
+        })
+        .collect()
+}
+
+// Gets the roster to carry into the next chapter once a chapter has been won
+pub fn next_roster<'a>(
+    roster: &serialization::Roster<'a>,
+    units: &[Unit<'a>],
+) -> serialization::Roster<'a> {
+    let player_units: Vec<_> = units
+        .iter()
+        .filter(|u| u.faction == serialization::Faction::Blue)
+        .collect();
+
+    // Units that sat this chapter out are carried over unchanged
+    let mut next = serialization::Roster {
+        units: roster
+            .units
+            .iter()
+            .filter(|r| !player_units.iter().any(|u| u.info.name == r.name))
+            .cloned()
+            .collect(),
+        fallen: roster.fallen.clone(),
+    };
+    for u in player_units {
+        if u.info.hp > ZERO_HP {
+            // Required units that fell earlier stop counting as fallen once they survive a chapter
+            next.fallen.retain(|name| *name != u.info.name);
+            next.units.push(u.info.clone());
+        } else {
+            next.fallen.push(u.info.name);
+        }
+    }
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use numeric_types::{accuracy_pts, hp, level, map_dist};
+
+    fn unit(name: &str, x: i32) -> serialization::Unit<'_> {
+        serialization::Unit {
+            name,
+            class: serialization::Class::Noble,
+            level: level(1),
+            max_hp: hp(10),
+            hp: hp(10),
+            attack: hp(5),
+            defense: hp(1),
+            accuracy: accuracy_pts(80),
+            evade: accuracy_pts(10),
+            critical: accuracy_pts(5),
+            movement: map_dist(3),
+            remaining_move: map_dist(3),
+            position: Vector {
+                x: map_dist(x),
+                y: ZERO_TILES,
+            },
+        }
+    }
+
+    // A unit that has gained a level and been wounded in an earlier chapter
+    fn veteran(name: &str) -> serialization::Unit<'_> {
+        serialization::Unit {
+            level: level(2),
+            max_hp: hp(12),
+            hp: hp(4),
+            remaining_move: ZERO_TILES,
+            ..unit(name, 5)
+        }
+    }
+
+    fn names<'a>(units: &[serialization::Unit<'a>]) -> Vec<&'a str> {
+        units.iter().map(|u| u.name).collect()
+    }
+
+    #[test]
+    fn new_campaigns_deploy_the_map_units() {
+        let map_units = [unit("Maylis", 0), unit("Gerard", 1)];
+        let deployed = deploy(&map_units, &serialization::Roster::default(), &[]);
+        assert_eq!(names(&deployed), ["Maylis", "Gerard"]);
+    }
+
+    #[test]
+    fn carried_units_keep_their_stats_and_heal() {
+        let roster = serialization::Roster {
+            units: vec![veteran("Maylis")],
+            fallen: Vec::new(),
+        };
+        let deployed = deploy(&[unit("Maylis", 0)], &roster, &[]);
+        assert_eq!(deployed[0].level, level(2));
+        assert_eq!(deployed[0].hp, hp(12));
+        assert_eq!(deployed[0].remaining_move, map_dist(3));
+        assert_eq!(deployed[0].position.x, ZERO_TILES);
+    }
+
+    #[test]
+    fn fallen_units_are_not_deployed() {
+        let roster = serialization::Roster {
+            units: Vec::new(),
+            fallen: vec!["Gerard"],
+        };
+        let deployed = deploy(&[unit("Maylis", 0), unit("Gerard", 1)], &roster, &[]);
+        assert_eq!(names(&deployed), ["Maylis"]);
+    }
+
+    #[test]
+    fn required_units_are_deployed_even_if_they_fell() {
+        let roster = serialization::Roster {
+            units: Vec::new(),
+            fallen: vec!["Maylis", "Gerard"],
+        };
+        let deployed = deploy(
+            &[unit("Maylis", 0), unit("Gerard", 1)],
+            &roster,
+            &["Maylis"],
+        );
+        assert_eq!(names(&deployed), ["Maylis"]);
+        assert_eq!(deployed[0].hp, hp(10));
+
+        let units = [Unit::new(deployed[0].clone(), serialization::Faction::Blue)];
+        let next = next_roster(&roster, &units);
+        assert_eq!(names(&next.units), ["Maylis"]);
+        assert_eq!(next.fallen, ["Gerard"]);
+    }
+
+    #[test]
+    fn units_that_sat_out_are_carried_over() {
+        let roster = serialization::Roster {
+            units: vec![veteran("Reserve"), veteran("Maylis")],
+            fallen: Vec::new(),
+        };
+        let units = [Unit::new(unit("Maylis", 0), serialization::Faction::Blue)];
+        let next = next_roster(&roster, &units);
+        assert_eq!(names(&next.units), ["Reserve", "Maylis"]);
+        assert_eq!(next.units[0].level, level(2));
+        assert_eq!(next.units[1].level, level(1));
+    }
+
+    #[test]
+    fn defeated_units_fall_and_enemies_are_left_out() {
+        let roster = serialization::Roster {
+            units: Vec::new(),
+            fallen: vec!["Earlier"],
+        };
+        let mut lost = Unit::new(unit("Gerard", 1), serialization::Faction::Blue);
+        lost.info.hp = ZERO_HP;
+        let units = [
+            Unit::new(unit("Maylis", 0), serialization::Faction::Blue),
+            lost,
+            Unit::new(unit("Enemy", 2), serialization::Faction::Red),
+        ];
+        let next = next_roster(&roster, &units);
+        assert_eq!(names(&next.units), ["Maylis"]);
+        assert_eq!(next.fallen, ["Earlier", "Gerard"]);
+    }
+}
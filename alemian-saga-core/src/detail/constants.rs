@@ -4,6 +4,16 @@ use numeric_types::*;
 pub const CURSOR_IMAGE: &str = "cursor.png";
 pub const INFO_BAR_IMAGE: &str = "infobar.png";
 pub const UNIT_INFO_BAR_IMAGE: &str = "unit-infobar.png";
+pub const DONE_IMAGE: &str = "done.png";
+pub const SAVE_FILE: &str = "save.sav";
+pub const KEYBINDINGS_FILE: &str = "keybindings.json";
+pub const LANGUAGE_FILE: &str = "language.txt";
+pub const EDGE_PANNING_FILE: &str = "edge-panning.txt";
+pub const LANGUAGE_DIRECTORY: &str = "language";
+pub const LANGUAGE_INDEX: &str = "index.lang";
+pub const DEFAULT_MAP: &str = "map";
+pub const CAMPAIGN_FILE: &str = "campaign.camp";
+pub const PROGRESS_FILE: &str = "progress.sav";
 
 pub const ZERO_TILES: MapDistance = map_dist(0);
 pub const ONE_TILE: MapDistance = map_dist(1);
@@ -28,4 +38,7 @@ pub const RIGHT: Vector<MapDistance> = Vector {
 
 pub const ZERO_HP: HitPoints = hp(0);
 
+pub const FIRST_TURN: Turns = turns(1);
+pub const ONE_TURN: Turns = turns(1);
+
 pub const BASE_EVADE_BONUS: AccuracyPoints = accuracy_pts(0);
@@ -0,0 +1,9 @@
+use crate::*;
+use detail::*;
+
+// Tracks the mouse while the map is being dragged
+pub struct Drag<D> {
+    pub start: Vector<D>,
+    pub screen_start: Vector<MapDistance>,
+    pub moved: bool,
+}
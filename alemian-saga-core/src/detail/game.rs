@@ -4,16 +4,26 @@ use detail::*;
 
 // Struct for holding game state
 pub struct Game<'a, 'b, P: Platform> {
-    pub platform: P,
+    pub platform: &'b P,
     pub cursor_pos: Vector<MapDistance>,
     pub map: &'b ndarray::Array2<Tile<'a, P>>,
     pub cursor_image: Option<P::Image>,
     pub infobar_image: Option<P::Image>,
     pub unit_infobar: Option<P::Image>,
+    // Drawn over units that have already acted this phase
+    pub done_image: Option<P::Image>,
     pub screen: Rectangle<MapDistance>,
     pub last_mouse_pan: P::Instant,
-    pub unit_images: std::collections::HashMap<serialization::Class, P::Image>,
+    pub drag: Option<Drag<P::MouseDistance>>,
+    pub unit_images:
+        std::collections::HashMap<(serialization::Faction, serialization::Class), P::Image>,
+    pub turn: Turns,
+    pub phase: Phase,
+    pub rng: Rng,
+    pub language: Language,
+    pub units: Vec<Unit<'a>>,
     highlighted_tiles: Vec<&'b Tile<'a, P>>,
+    selected_pos: Option<Vector<MapDistance>>,
 }
 
 fn get_map_tile<'a, 'b, P: Platform>(
@@ -23,14 +33,23 @@ fn get_map_tile<'a, 'b, P: Platform>(
     &map[[pos.y.value as usize, pos.x.value as usize]]
 }
 
+// Negative positions wrap around to huge indices when cast, so they are also reported as missing
+fn try_get_map_tile<'a, 'b, P: Platform>(
+    map: &'b ndarray::Array2<Tile<'a, P>>,
+    pos: Vector<MapDistance>,
+) -> Option<&'b Tile<'a, P>> {
+    map.get((pos.y.value as usize, pos.x.value as usize))
+}
+
 impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
     pub fn new(
-        platform: P,
+        platform: &'b P,
         map: &'b mut ndarray::Array2<Tile<'a, P>>,
         cursor_image: Option<P::Image>,
         infobar_image: Option<P::Image>,
         unit_infobar: Option<P::Image>,
-        last_mouse_pan: P::Instant,
+        seed: u64,
+        language: Language,
     ) -> Self {
         let (rows, columns) = map.dim();
         Self {
@@ -43,6 +62,7 @@ impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
             cursor_image,
             infobar_image,
             unit_infobar,
+            done_image: None,
             screen: Rectangle {
                 top_left: Vector {
                     x: ZERO_TILES,
@@ -53,9 +73,16 @@ impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
                     y: numeric_types::map_dist(rows as i32),
                 },
             },
-            last_mouse_pan,
+            last_mouse_pan: P::now(),
+            drag: None,
             unit_images: std::collections::HashMap::new(),
+            turn: FIRST_TURN,
+            phase: Phase::Player,
+            rng: Rng::new(seed),
+            language,
+            units: Vec::new(),
             highlighted_tiles: Vec::new(),
+            selected_pos: None,
         }
     }
 
@@ -66,7 +93,7 @@ impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
     }
 
     fn try_get_tile(&self, pos: Vector<MapDistance>) -> Option<&'b Tile<'a, P>> {
-        self.map.get((pos.y.value as usize, pos.x.value as usize))
+        try_get_map_tile(self.map, pos)
     }
 
     pub fn get_tile(&self, pos: Vector<MapDistance>) -> &'b Tile<'a, P> {
@@ -97,12 +124,162 @@ impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
         Some(Vector::<MapDistance>::from(pos_on_screen.lossy_cast::<i32>()?) + self.screen.top_left)
     }
 
+    // Moves a screen position so that the screen stays within the bounds of the map
+    fn clamp_screen_pos(&self, top_left: Vector<MapDistance>) -> Vector<MapDistance> {
+        let max = self.get_map_size() - self.screen.size;
+        Vector {
+            x: utility::partial_ord_max(ZERO_TILES, utility::partial_ord_min(top_left.x, max.x)),
+            y: utility::partial_ord_max(ZERO_TILES, utility::partial_ord_min(top_left.y, max.y)),
+        }
+    }
+
+    // Moves the visible part of the map, keeping it within the bounds of the map
+    // Returns true if the screen moved
+    pub fn pan_to(&mut self, top_left: Vector<MapDistance>) -> bool {
+        let clamped = self.clamp_screen_pos(top_left);
+        if clamped == self.screen.top_left {
+            false
+        } else {
+            self.screen.top_left = clamped;
+            self.redraw();
+            true
+        }
+    }
+
+    // Moves the screen so that a tile is drawn under a point on the screen
+    fn anchor_tile(&mut self, pointer: Vector<P::MouseDistance>, tile: Vector<MapDistance>) {
+        let fraction = pointer
+            .cast::<P::ScreenDistance>()
+            .piecewise_divide(self.platform.get_screen_size());
+        let offset = self
+            .screen
+            .size
+            .lossy_cast::<P::ScreenDistance>()
+            .and_then(|size| fraction.piecewise_multiply(size).lossy_cast::<i32>());
+        if let Some(offset) = offset {
+            self.screen.top_left =
+                self.clamp_screen_pos(tile - Vector::<MapDistance>::from(offset));
+        }
+        // The pointer is over the anchored tile, so the cursor belongs there too
+        let map_size = self.get_map_size();
+        if tile.x >= ZERO_TILES
+            && tile.y >= ZERO_TILES
+            && tile.x < map_size.x
+            && tile.y < map_size.y
+        {
+            self.cursor_pos = tile;
+        }
+    }
+
+    // Zooms in by one tile, keeping either the tile under the pointer or the cursor in view
+    pub fn zoom_in(&mut self, pointer: Option<Vector<P::MouseDistance>>) {
+        let anchor = pointer.and_then(|p| Some((p, self.get_map_pos(p)?)));
+        let tile_size = self.get_tile_size();
+        let size = &mut self.screen.size;
+        let cursor_pos_on_screen = self.cursor_pos - self.screen.top_left;
+        if tile_size.x >= tile_size.y && size.y > ONE_TILE {
+            size.y -= ONE_TILE;
+            if cursor_pos_on_screen.y > size.y / 2 {
+                self.screen.top_left.y += ONE_TILE;
+            }
+        }
+        if tile_size.y >= tile_size.x && size.x > ONE_TILE {
+            size.x -= ONE_TILE;
+            if cursor_pos_on_screen.x > size.x / 2 {
+                self.screen.top_left.x += ONE_TILE;
+            }
+        }
+        if let Some((pointer, tile)) = anchor {
+            self.anchor_tile(pointer, tile);
+        }
+        self.redraw();
+    }
+
+    // Zooms out by one tile, keeping either the tile under the pointer or the cursor in view
+    pub fn zoom_out(&mut self, pointer: Option<Vector<P::MouseDistance>>) {
+        let anchor = pointer.and_then(|p| Some((p, self.get_map_pos(p)?)));
+        let tile_size = self.get_tile_size();
+        let map_size = self.get_map_size();
+        let cursor_pos_on_screen = self.cursor_pos - self.screen.top_left;
+        let size = self.screen.size;
+        if size.y < map_size.y && (tile_size.y >= tile_size.x || size.x == map_size.x) {
+            self.screen.size.y += ONE_TILE;
+            if self.screen.bottom() > map_size.y
+                || self.screen.top() > ZERO_TILES
+                    && cursor_pos_on_screen.y < self.screen.height() / 2
+            {
+                self.screen.top_left.y -= ONE_TILE;
+            }
+        }
+        if size.x < map_size.x && (tile_size.x >= tile_size.y || size.y == map_size.y) {
+            self.screen.size.x += ONE_TILE;
+            if self.screen.right() > map_size.x
+                || self.screen.left() > ZERO_TILES && cursor_pos_on_screen.x < size.x / 2
+            {
+                self.screen.top_left.x -= ONE_TILE;
+            }
+        }
+        if let Some((pointer, tile)) = anchor {
+            self.anchor_tile(pointer, tile);
+        }
+        self.redraw();
+    }
+
+    pub fn start_drag(&mut self, pos: Vector<P::MouseDistance>) {
+        self.drag = Some(Drag {
+            start: pos,
+            screen_start: self.screen.top_left,
+            moved: false,
+        });
+    }
+
+    // Pans the map so that it follows the mouse while it is being dragged
+    pub fn drag_to(&mut self, pos: Vector<P::MouseDistance>) {
+        if let Some(drag) = &self.drag {
+            let offset = (drag.start.cast::<P::ScreenDistance>() - pos.cast())
+                .piecewise_divide(self.get_tile_size())
+                .lossy_cast::<i32>();
+            let screen_start = drag.screen_start;
+            if let Some(offset) = offset {
+                if self.pan_to(screen_start + Vector::<MapDistance>::from(offset)) {
+                    if let Some(drag) = &mut self.drag {
+                        drag.moved = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // Stops dragging the map
+    // Returns true if the map was moved, in which case the mouse release isn't a click
+    pub fn end_drag(&mut self) -> bool {
+        self.drag.take().is_some_and(|drag| drag.moved)
+    }
+
+    // Moves the cursor to the tile at a point on the screen and selects it
+    pub fn select_at(&mut self, pos: Vector<P::MouseDistance>) {
+        if let Some(p) = self.get_map_pos(pos) {
+            if self.try_get_tile(p).is_some() {
+                if p != self.cursor_pos {
+                    self.move_cursor(p);
+                }
+                self.select_tile();
+            }
+        }
+    }
+
     pub fn move_cursor(&mut self, pos: Vector<MapDistance>) {
-        let old_pos = self.cursor_pos;
-        self.draw_tile(self.get_tile(old_pos), old_pos);
-        self.cursor_pos = pos;
-        self.draw_cursor();
-        self.draw_infobar();
+        if self.selected_pos.is_some() {
+            // The movement path follows the cursor, so the whole path needs to be redrawn
+            self.cursor_pos = pos;
+            self.redraw();
+        } else {
+            let old_pos = self.cursor_pos;
+            self.draw_tile(self.get_tile(old_pos), old_pos);
+            self.cursor_pos = pos;
+            self.draw_cursor();
+            self.draw_infobar();
+        }
     }
 
     fn draw_cursor(&self) {
@@ -135,12 +312,14 @@ impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
         let max_width = size.x * P::ScreenDistance::from_f64(0.75).unwrap_or_else(|| 1.into());
         let stat_y = utility::multiply_frac(height, 5, 8);
 
-        if let Some(unit) = tile.unit.get() {
+        if let Some(id) = tile.unit.get() {
+            let unit = &self.units[id];
             self.platform
                 .attempt_draw(self.unit_infobar.as_ref(), &position);
-            self.platform.draw_text(unit.name, offset, max_width);
+            self.platform.draw_text(unit.info.name, offset, max_width);
+            let level_args = [("level", Arg::from(unit.info.level.value))];
             self.platform.draw_text(
-                format!("lv {}", unit.level.value).as_str(),
+                self.language.format("level", &level_args).as_str(),
                 Vector {
                     x: offset_scalar,
                     y: stat_y,
@@ -148,7 +327,11 @@ impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
                 size.y,
             );
             let hp_x = utility::multiply_frac(size.y, 5, 2);
-            let hp_str = format!("{}/{}", unit.hp.value, unit.hp.value);
+            let hp_args = [
+                ("hp", Arg::from(unit.info.hp.value)),
+                ("max_hp", Arg::from(unit.info.max_hp.value)),
+            ];
+            let hp_str = self.language.format("hp", &hp_args);
             self.platform
                 .draw_text(hp_str.as_str(), Vector { x: hp_x, y: stat_y }, size.y);
         } else {
@@ -156,7 +339,8 @@ impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
 
             self.platform
                 .attempt_draw(self.infobar_image.as_ref(), &position);
-            self.platform.draw_text(info.name, offset, max_width);
+            self.platform
+                .draw_text(self.language.get(info.name), offset, max_width);
             let stat_width = height * P::ScreenDistance::from(13) / P::ScreenDistance::from(16);
             let move_pos = Vector {
                 x: utility::multiply_frac(height, 3, 4),
@@ -185,6 +369,53 @@ impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
         }
     }
 
+    // Draws a message in place of the info bar
+    pub fn draw_prompt(&self, text: &str) {
+        let height = self.platform.get_height() / P::ScreenDistance::from(15);
+        let width = self.platform.get_width();
+        let position = Rectangle {
+            top_left: Vector {
+                x: 0.into(),
+                y: 0.into(),
+            },
+            size: Vector {
+                x: width,
+                y: height,
+            },
+        };
+        self.platform
+            .attempt_draw(self.infobar_image.as_ref(), &position);
+        let offset_scalar = height / P::ScreenDistance::from(4);
+        let offset = Vector {
+            x: offset_scalar,
+            y: offset_scalar,
+        };
+        self.platform
+            .draw_text(text, offset, width - offset_scalar - offset_scalar);
+    }
+
+    // Draws lines of text on a panel in the middle of the screen
+    pub fn draw_panel(&self, lines: &[String]) {
+        let line_height = self.platform.get_height() / P::ScreenDistance::from(15);
+        let screen_size = self.platform.get_screen_size();
+        let size = Vector {
+            x: utility::multiply_frac(screen_size.x, 3, 4),
+            y: line_height * P::ScreenDistance::from(lines.len() as i32 + 1),
+        };
+        let top_left = (screen_size - size) / P::ScreenDistance::from(2);
+        self.platform
+            .attempt_draw(self.unit_infobar.as_ref(), &Rectangle { top_left, size });
+        let margin = line_height / P::ScreenDistance::from(2);
+        let max_width = size.x - margin - margin;
+        for (i, line) in lines.iter().enumerate() {
+            let offset = Vector {
+                x: top_left.x + margin,
+                y: top_left.y + line_height * P::ScreenDistance::from(i as i32 + 1),
+            };
+            self.platform.draw_text(line.as_str(), offset, max_width);
+        }
+    }
+
     pub fn redraw(&self) {
         let top_left = self.screen.top_left;
         let top_left_index = top_left.lossy_cast::<usize>().expect("Failed cast");
@@ -201,6 +432,7 @@ impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
             } + top_left;
             self.draw_tile(t, map_pos);
         }
+        self.draw_path();
         self.draw_cursor();
         self.draw_infobar();
     }
@@ -208,9 +440,16 @@ impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
     fn draw_tile(&self, tile: &Tile<'a, P>, pos: Vector<MapDistance>) {
         let screen_pos = self.get_screen_pos(pos);
         self.platform.attempt_draw(tile.image, &screen_pos);
-        if let Some(u) = tile.unit.get() {
-            self.platform
-                .attempt_draw(self.unit_images.get(&u.class), &screen_pos);
+        if let Some(id) = tile.unit.get() {
+            let u = &self.units[id];
+            self.platform.attempt_draw(
+                self.unit_images.get(&(u.faction, u.info.class)),
+                &screen_pos,
+            );
+            if u.done {
+                self.platform
+                    .attempt_draw(self.done_image.as_ref(), &screen_pos);
+            }
         }
         if tile.remaining_move.get() >= ZERO_TILES {
             self.platform.draw_rectangle(
@@ -222,57 +461,575 @@ impl<'a, 'b, P: Platform> Game<'a, 'b, P> {
         }
     }
 
+    // Draws the path that the selected unit would take to reach the cursor
+    fn draw_path(&self) {
+        if self.selected_pos.is_none() {
+            return;
+        }
+        if let Some(path) = self.get_path(self.cursor_pos) {
+            let tile_size = self.get_tile_size();
+            let thickness = tile_size / P::ScreenDistance::from(4);
+            let margin = (tile_size - thickness) / P::ScreenDistance::from(2);
+            for step in path.windows(2) {
+                let from = self.get_screen_pos(step[0]).top_left;
+                let to = self.get_screen_pos(step[1]).top_left;
+                let left = utility::partial_ord_min(from.x, to.x);
+                let top = utility::partial_ord_min(from.y, to.y);
+                let right = utility::partial_ord_max(from.x, to.x);
+                let bottom = utility::partial_ord_max(from.y, to.y);
+                self.platform.draw_rectangle(
+                    left + margin.x,
+                    top + margin.y,
+                    right - left + thickness.x,
+                    bottom - top + thickness.y,
+                );
+            }
+        }
+    }
+
     fn queue_for_processing<C: compare::Compare<Vector<MapDistance>>>(
         &self,
         tiles_to_process: &mut binary_heap_plus::BinaryHeap<Vector<MapDistance>, C>,
-        pos: Vector<MapDistance>,
-        remaining_move: MapDistance,
+        unit: UnitId,
+        from: Vector<MapDistance>,
+        direction: Vector<MapDistance>,
     ) {
+        let pos = from + direction;
         if let Some(t) = self.try_get_tile(pos) {
-            let rem = remaining_move - t.info.move_cost;
+            // Units can move past allies, but not past enemies
+            if let Some(other) = t.unit.get() {
+                if !self.units[unit].is_allied_with(&self.units[other]) {
+                    return;
+                }
+            }
+            let rem = self.get_tile(from).remaining_move.get() - t.info.move_cost;
             if rem > t.remaining_move.get() {
                 t.remaining_move.set(rem);
+                t.previous.set(Some(from));
                 tiles_to_process.push(pos);
             }
         }
     }
 
-    pub fn select_tile(&mut self) {
+    // Gets the step-by-step path that the selected unit would take to reach a tile
+    pub fn get_path(&self, dest: Vector<MapDistance>) -> Option<Vec<Vector<MapDistance>>> {
+        if self.try_get_tile(dest)?.remaining_move.get() < ZERO_TILES {
+            return None;
+        }
+        let mut path = vec![dest];
+        while let Some(p) = self.get_tile(*path.last()?).previous.get() {
+            path.push(p);
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    fn clear_highlights(&mut self) {
         for t in &self.highlighted_tiles {
             t.remaining_move.set(UNREACHABLE);
+            t.previous.set(None);
         }
         self.highlighted_tiles.clear();
-        let start_tile = self.get_tile(self.cursor_pos);
-        if let Some(u) = start_tile.unit.get() {
-            let map = self.map;
-            let mut tiles_to_process = binary_heap_plus::BinaryHeap::new_by(
-                |a: &Vector<MapDistance>, b: &Vector<MapDistance>| {
-                    get_map_tile(map, *a)
-                        .remaining_move
-                        .cmp(&get_map_tile(map, *b).remaining_move)
-                },
-            );
-            start_tile.remaining_move.set(u.remaining_move);
-            tiles_to_process.push(self.cursor_pos);
-            while let Some(p) = tiles_to_process.pop() {
-                let tile = self.get_tile(p);
-                self.highlighted_tiles.push(tile);
-                let remaining_move = tile.remaining_move.get();
-                self.queue_for_processing(&mut tiles_to_process, p + UP, remaining_move);
-                self.queue_for_processing(&mut tiles_to_process, p + DOWN, remaining_move);
-                self.queue_for_processing(&mut tiles_to_process, p + LEFT, remaining_move);
-                self.queue_for_processing(&mut tiles_to_process, p + RIGHT, remaining_move);
+    }
+
+    fn highlight_reachable_tiles(&mut self, start: Vector<MapDistance>, unit: UnitId) {
+        let map = self.map;
+        let mut tiles_to_process = binary_heap_plus::BinaryHeap::new_by(
+            |a: &Vector<MapDistance>, b: &Vector<MapDistance>| {
+                get_map_tile(map, *a)
+                    .remaining_move
+                    .cmp(&get_map_tile(map, *b).remaining_move)
+            },
+        );
+        self.get_tile(start)
+            .remaining_move
+            .set(self.units[unit].info.remaining_move);
+        tiles_to_process.push(start);
+        while let Some(p) = tiles_to_process.pop() {
+            self.highlighted_tiles.push(self.get_tile(p));
+            self.queue_for_processing(&mut tiles_to_process, unit, p, UP);
+            self.queue_for_processing(&mut tiles_to_process, unit, p, DOWN);
+            self.queue_for_processing(&mut tiles_to_process, unit, p, LEFT);
+            self.queue_for_processing(&mut tiles_to_process, unit, p, RIGHT);
+        }
+    }
+
+    // Selects the unit under the cursor, or moves the selected unit to the cursor
+    pub fn select_tile(&mut self) {
+        match self.selected_pos {
+            Some(p) => {
+                let selected = self.get_tile(p).unit.get();
+                let target = self.get_tile(self.cursor_pos).unit.get();
+                match (selected, target) {
+                    (Some(u), Some(enemy)) if !self.units[u].is_allied_with(&self.units[enemy]) => {
+                        self.attack(p, u, enemy)
+                    }
+                    _ => self.move_selected_unit(p),
+                }
+            }
+            None => {
+                let unit = self.get_tile(self.cursor_pos).unit.get();
+                let can_act =
+                    |id: &UnitId| self.units[*id].acts_in(self.phase) && !self.units[*id].done;
+                if let Some(u) = unit.filter(can_act) {
+                    self.highlight_reachable_tiles(self.cursor_pos, u);
+                    self.selected_pos = Some(self.cursor_pos);
+                    self.redraw();
+                }
             }
         }
+    }
+
+    // Moves a unit to a highlighted tile, returning false if the tile can't be moved to
+    fn commit_move(&mut self, from: Vector<MapDistance>, to: Vector<MapDistance>) -> bool {
+        let start = self.get_tile(from);
+        let dest = self.get_tile(to);
+        let remaining_move = dest.remaining_move.get();
+        if remaining_move < ZERO_TILES || from != to && dest.unit.get().is_some() {
+            return false;
+        }
+        if let Some(id) = start.unit.take() {
+            let unit = &mut self.units[id];
+            unit.info.remaining_move = remaining_move;
+            unit.info.position = to;
+            unit.done = true;
+            dest.unit.set(Some(id));
+        }
+        true
+    }
+
+    fn finish_action(&mut self) {
+        self.selected_pos = None;
+        self.clear_highlights();
+        self.redraw();
+    }
+
+    fn move_selected_unit(&mut self, from: Vector<MapDistance>) {
+        if self.commit_move(from, self.cursor_pos) {
+            self.finish_action();
+        }
+    }
+
+    // Moves the selected unit next to the enemy under the cursor and attacks it
+    fn attack(&mut self, from: Vector<MapDistance>, attacker: UnitId, defender: UnitId) {
+        let target_pos = self.cursor_pos;
+        let can_stand_on = |p: &Vector<MapDistance>| match self.try_get_tile(*p) {
+            Some(t) => {
+                t.remaining_move.get() >= ZERO_TILES && (t.unit.get().is_none() || *p == from)
+            }
+            None => false,
+        };
+        let standing_pos = [UP, DOWN, LEFT, RIGHT]
+            .iter()
+            .map(|d| target_pos + *d)
+            .filter(can_stand_on)
+            .max_by_key(|p| self.get_tile(*p).remaining_move.get());
+        if let Some(p) = standing_pos {
+            if !self.commit_move(from, p) {
+                return;
+            }
+            let attacker_tile = self.get_tile(p);
+            let defender_tile = self.get_tile(target_pos);
+            let attacker_stats = self.units[attacker].combatant(attacker_tile.info);
+            let defender_stats = self.units[defender].combatant(defender_tile.info);
+            let rng = &mut self.rng;
+            let outcome = combat::resolve(&attacker_stats, &defender_stats, || rng.roll());
+            self.units[attacker].info.hp = outcome.attacker_hp;
+            self.units[defender].info.hp = outcome.defender_hp;
+            if outcome.attacker_hp <= ZERO_HP {
+                attacker_tile.unit.set(None);
+            }
+            if outcome.defender_hp <= ZERO_HP {
+                defender_tile.unit.set(None);
+            }
+            self.finish_action();
+        }
+    }
+
+    // Deselects the selected unit without moving it
+    pub fn cancel(&mut self) {
+        if self.selected_pos.take().is_some() {
+            self.clear_highlights();
+            self.redraw();
+        }
+    }
+
+    // Switches to the next phase, refreshing the units that act in it
+    fn next_phase(&mut self) {
+        self.phase = self.phase.next();
+        if self.phase == Phase::Player {
+            self.turn += ONE_TURN;
+        }
+        let phase = self.phase;
+        // Defeated units stay in the roster so that saved units line up, but never act again
+        let can_act = |u: &&mut Unit| u.acts_in(phase) && u.info.hp > ZERO_HP;
+        let map = self.map;
+        for u in self.units.iter_mut().filter(can_act) {
+            u.refresh();
+            if let Some(t) = try_get_map_tile(map, u.info.position) {
+                u.heal(t.info.heal);
+            }
+        }
+    }
+
+    // Ends the player phase and runs the phases of the other factions
+    pub fn end_turn(&mut self) {
+        if self.phase != Phase::Player {
+            return;
+        }
+        self.cancel();
+        self.next_phase();
+
+        // Enemies and allies don't take any actions yet, so their phases end immediately
+        while self.phase != Phase::Player {
+            self.next_phase();
+        }
         self.redraw();
     }
 
-    pub fn add_unit(&mut self, unit: &'a serialization::Unit<'a>) {
-        if let Some(t) = self.map.get((
-            unit.position.y.value as usize,
-            unit.position.x.value as usize,
-        )) {
-            t.unit.set(Some(unit))
+    // Captures the parts of the game state that can change during gameplay
+    pub fn save<'c>(
+        &self,
+        map: &'c str,
+        roster: &serialization::Roster<'c>,
+    ) -> serialization::Save<'c> {
+        let units = self.units.iter().map(|u| serialization::SavedUnit {
+            level: u.info.level,
+            max_hp: u.info.max_hp,
+            hp: u.info.hp,
+            remaining_move: u.info.remaining_move,
+            position: u.info.position,
+            done: u.done,
+        });
+        serialization::Save {
+            map,
+            turn: self.turn,
+            seed: self.rng.state(),
+            units: units.collect(),
+            roster: roster.clone(),
         }
     }
+
+    // Restores the game to a previously saved state of the same map
+    // Games are only saved during the player phase, so that is where a loaded game resumes
+    pub fn load(&mut self, save: &serialization::Save) -> Result<(), utility::Error> {
+        if save.units.len() != self.units.len() {
+            return Err(utility::Error::from("Saved game does not match the map"));
+        }
+        // Every unit is checked before anything changes, so a bad save leaves the game as it was
+        let mut occupied = std::collections::HashMap::new();
+        for (unit, saved) in self.units.iter().zip(save.units.iter()) {
+            let p = saved.position;
+            if self.try_get_tile(p).is_none() {
+                return Err(utility::Error::from(format!(
+                    "Unit {} was saved outside of the map at row {}, column {}",
+                    unit.info.name, p.y.value, p.x.value
+                )));
+            }
+            if saved.hp > ZERO_HP {
+                if let Some(other) = occupied.insert((p.x.value, p.y.value), unit.info.name) {
+                    return Err(utility::Error::from(format!(
+                        "Units {} and {} were saved on the same tile",
+                        other, unit.info.name
+                    )));
+                }
+            }
+        }
+        self.cancel();
+        for t in self.map.iter() {
+            t.unit.set(None);
+        }
+        for (id, (unit, saved)) in self.units.iter_mut().zip(save.units.iter()).enumerate() {
+            unit.info.level = saved.level;
+            unit.info.max_hp = saved.max_hp;
+            unit.info.hp = saved.hp;
+            unit.info.remaining_move = saved.remaining_move;
+            unit.info.position = saved.position;
+            unit.done = saved.done;
+            if saved.hp > ZERO_HP {
+                get_map_tile(self.map, saved.position).unit.set(Some(id));
+            }
+        }
+        self.turn = save.turn;
+        self.phase = Phase::Player;
+        self.rng = Rng::new(save.seed);
+        self.redraw();
+        Ok(())
+    }
+
+    // Places a unit on the map
+    // Saved games rely on units being added in the same order every time, so a unit that can't be
+    // placed is an error instead of being left out
+    pub fn add_unit(&mut self, unit: Unit<'a>) -> Result<(), utility::Error> {
+        let position = unit.info.position;
+        let tile = self.try_get_tile(position).ok_or_else(|| {
+            utility::Error::from(format!(
+                "Unit {} is outside of the map at row {}, column {}",
+                unit.info.name, position.y.value, position.x.value
+            ))
+        })?;
+        tile.unit.set(Some(self.units.len()));
+        self.units.push(unit);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use numeric_types::{accuracy_pts, hp, level, map_dist};
+
+    const fn tile_type(name: &'static str, move_cost: i32) -> serialization::TileType<'static> {
+        serialization::TileType {
+            image: "plain.png",
+            name,
+            defense: hp(0),
+            evade: accuracy_pts(0),
+            move_cost: map_dist(move_cost),
+            heal: hp(0),
+        }
+    }
+
+    const PLAIN: serialization::TileType = tile_type("plain", 1);
+    const FOREST: serialization::TileType = tile_type("forest", 4);
+
+    fn pos(x: i32, y: i32) -> Vector<MapDistance> {
+        Vector {
+            x: map_dist(x),
+            y: map_dist(y),
+        }
+    }
+
+    fn unit(
+        name: &'static str,
+        faction: serialization::Faction,
+        position: Vector<MapDistance>,
+        movement: i32,
+    ) -> Unit<'static> {
+        let info = serialization::Unit {
+            name,
+            class: serialization::Class::Noble,
+            level: level(1),
+            max_hp: hp(10),
+            hp: hp(10),
+            attack: hp(5),
+            defense: hp(1),
+            accuracy: accuracy_pts(80),
+            evade: accuracy_pts(10),
+            critical: accuracy_pts(5),
+            movement: map_dist(movement),
+            remaining_move: map_dist(movement),
+            position,
+        };
+        Unit::new(info, faction)
+    }
+
+    // Runs a check against a game on a 3x3 map, where forest tiles are marked with 'f'
+    fn with_game<F: FnOnce(&mut Game<'_, '_, headless::Headless>)>(
+        rows: [&str; 3],
+        units: Vec<Unit<'static>>,
+        check: F,
+    ) {
+        let screen_size = Vector { x: 300.0, y: 300.0 };
+        let platform = headless::Headless::new(Vec::new(), std::env::temp_dir(), screen_size);
+        let mut map = ndarray::Array2::from_shape_fn((3, 3), |(row, column)| {
+            match rows[row].as_bytes()[column] {
+                b'f' => tile::make_tile(None, &FOREST),
+                _ => tile::make_tile(None, &PLAIN),
+            }
+        });
+        let language = Language::with_strings(&[]);
+        let mut game = Game::new(&platform, &mut map, None, None, None, 0, language);
+        for u in units {
+            game.add_unit(u).unwrap_or_else(|e| panic!("{}", e.msg));
+        }
+        check(&mut game);
+    }
+
+    fn maylis(movement: i32) -> Unit<'static> {
+        unit("Maylis", serialization::Faction::Blue, pos(0, 0), movement)
+    }
+
+    // Selects the unit at a position and then selects a second tile
+    fn select_then(
+        game: &mut Game<'_, '_, headless::Headless>,
+        from: Vector<MapDistance>,
+        to: Vector<MapDistance>,
+    ) {
+        game.cursor_pos = from;
+        game.select_tile();
+        game.cursor_pos = to;
+    }
+
+    #[test]
+    fn selecting_a_highlighted_tile_moves_the_unit() {
+        with_game(["...", "...", "..."], vec![maylis(3)], |game| {
+            select_then(game, pos(0, 0), pos(1, 1));
+            game.select_tile();
+            assert_eq!(game.units[0].info.position, pos(1, 1));
+            assert_eq!(game.units[0].info.remaining_move, map_dist(1));
+            assert!(game.units[0].done);
+            assert_eq!(game.get_tile(pos(1, 1)).unit.get(), Some(0));
+            assert_eq!(game.get_tile(pos(0, 0)).unit.get(), None);
+            assert!(game.selected_pos.is_none());
+            assert!(game.highlighted_tiles.is_empty());
+        });
+    }
+
+    #[test]
+    fn selecting_a_tile_out_of_reach_does_nothing() {
+        with_game(["...", "...", "..."], vec![maylis(1)], |game| {
+            select_then(game, pos(0, 0), pos(2, 0));
+            game.select_tile();
+            assert_eq!(game.units[0].info.position, pos(0, 0));
+            assert!(!game.units[0].done);
+            assert_eq!(game.get_tile(pos(0, 0)).unit.get(), Some(0));
+            assert_eq!(game.selected_pos, Some(pos(0, 0)));
+        });
+    }
+
+    #[test]
+    fn cancelling_leaves_the_unit_in_place() {
+        with_game(["...", "...", "..."], vec![maylis(3)], |game| {
+            select_then(game, pos(0, 0), pos(1, 1));
+            game.cancel();
+            assert_eq!(game.units[0].info.position, pos(0, 0));
+            assert!(!game.units[0].done);
+            assert!(game.selected_pos.is_none());
+            assert_eq!(game.get_tile(pos(1, 1)).remaining_move.get(), UNREACHABLE);
+
+            // The unit can still be selected and moved afterwards
+            select_then(game, pos(0, 0), pos(1, 0));
+            game.select_tile();
+            assert_eq!(game.units[0].info.position, pos(1, 0));
+        });
+    }
+
+    #[test]
+    fn units_that_are_done_cannot_be_selected() {
+        with_game(["...", "...", "..."], vec![maylis(3)], |game| {
+            game.units[0].done = true;
+            game.cursor_pos = pos(0, 0);
+            game.select_tile();
+            assert!(game.selected_pos.is_none());
+        });
+    }
+
+    fn path(
+        game: &Game<'_, '_, headless::Headless>,
+        dest: Vector<MapDistance>,
+    ) -> Option<Vec<(i32, i32)>> {
+        let path = game.get_path(dest)?;
+        Some(path.iter().map(|p| (p.x.value, p.y.value)).collect())
+    }
+
+    #[test]
+    fn paths_take_the_cheapest_route() {
+        // Going straight through the forest costs 5, but going around it only costs 4
+        with_game([".f.", "...", "..."], vec![maylis(5)], |game| {
+            select_then(game, pos(0, 0), pos(2, 0));
+            let expected = vec![(0, 0), (0, 1), (1, 1), (2, 1), (2, 0)];
+            assert_eq!(path(game, pos(2, 0)), Some(expected));
+            assert_eq!(path(game, pos(1, 0)), Some(vec![(0, 0), (1, 0)]));
+        });
+    }
+
+    #[test]
+    fn paths_go_around_enemies() {
+        let units = vec![
+            maylis(4),
+            unit("Boss", serialization::Faction::Red, pos(1, 0), 3),
+        ];
+        with_game(["...", "...", "..."], units, |game| {
+            select_then(game, pos(0, 0), pos(2, 0));
+            let expected = vec![(0, 0), (0, 1), (1, 1), (2, 1), (2, 0)];
+            assert_eq!(path(game, pos(2, 0)), Some(expected));
+            assert_eq!(path(game, pos(1, 0)), None);
+        });
+    }
+
+    #[test]
+    fn paths_go_through_allies() {
+        let units = vec![
+            maylis(2),
+            unit("Gerard", serialization::Faction::Blue, pos(1, 0), 3),
+        ];
+        with_game(["...", "...", "..."], units, |game| {
+            select_then(game, pos(0, 0), pos(2, 0));
+            assert_eq!(path(game, pos(2, 0)), Some(vec![(0, 0), (1, 0), (2, 0)]));
+        });
+    }
+
+    #[test]
+    fn tiles_out_of_reach_have_no_path() {
+        with_game(["...", "...", "..."], vec![maylis(1)], |game| {
+            assert_eq!(path(game, pos(1, 0)), None);
+            select_then(game, pos(0, 0), pos(2, 2));
+            assert_eq!(path(game, pos(2, 2)), None);
+            assert_eq!(path(game, pos(3, 0)), None);
+        });
+    }
+
+    fn save_with<F: Fn(&mut [serialization::SavedUnit])>(
+        game: &Game<'_, '_, headless::Headless>,
+        change: F,
+    ) -> serialization::Save<'static> {
+        let mut save = game.save("map", &serialization::Roster::default());
+        change(&mut save.units);
+        save
+    }
+
+    #[test]
+    fn saved_games_load_units_where_they_were() {
+        let units = vec![
+            maylis(3),
+            unit("Boss", serialization::Faction::Red, pos(2, 2), 3),
+        ];
+        with_game(["...", "...", "..."], units, |game| {
+            let save = save_with(game, |units| units[0].position = pos(1, 2));
+            game.load(&save).unwrap_or_else(|e| panic!("{}", e.msg));
+            assert_eq!(game.units[0].info.position, pos(1, 2));
+            assert_eq!(game.get_tile(pos(1, 2)).unit.get(), Some(0));
+            assert_eq!(game.get_tile(pos(0, 0)).unit.get(), None);
+        });
+    }
+
+    #[test]
+    fn saved_games_with_units_off_the_map_are_rejected() {
+        let units = vec![
+            maylis(3),
+            unit("Boss", serialization::Faction::Red, pos(2, 2), 3),
+        ];
+        with_game(["...", "...", "..."], units, |game| {
+            for bad in [pos(3, 0), pos(0, -1)].iter() {
+                let save = save_with(game, |units| units[1].position = *bad);
+                assert!(game.load(&save).is_err());
+                assert_eq!(game.units[1].info.position, pos(2, 2));
+                assert_eq!(game.get_tile(pos(2, 2)).unit.get(), Some(1));
+            }
+            // Ending the turn still works, since the rejected save changed nothing
+            game.end_turn();
+        });
+    }
+
+    #[test]
+    fn saved_games_with_units_on_the_same_tile_are_rejected() {
+        let units = vec![
+            maylis(3),
+            unit("Boss", serialization::Faction::Red, pos(2, 2), 3),
+        ];
+        with_game(["...", "...", "..."], units, |game| {
+            let save = save_with(game, |units| units[1].position = pos(0, 0));
+            assert!(game.load(&save).is_err());
+            assert_eq!(game.get_tile(pos(0, 0)).unit.get(), Some(0));
+
+            // Defeated units aren't on a tile, so they can share one
+            let save = save_with(game, |units| {
+                units[1].position = pos(0, 0);
+                units[1].hp = ZERO_HP;
+            });
+            assert!(game.load(&save).is_ok());
+            assert_eq!(game.get_tile(pos(0, 0)).unit.get(), Some(0));
+        });
+    }
 }
@@ -1,4 +1,6 @@
-#[derive(serde::Deserialize)]
+use crate::*;
+
+#[derive(serde::Deserialize, serde::Serialize)]
 #[allow(non_snake_case)]
 pub struct Keybindings<'a> {
     #[serde(borrow, default)]
@@ -15,4 +17,44 @@ pub struct Keybindings<'a> {
     pub ZoomOut: Vec<&'a str>,
     #[serde(default)]
     pub Select: Vec<&'a str>,
+    #[serde(default)]
+    pub Cancel: Vec<&'a str>,
+    #[serde(default)]
+    pub EndTurn: Vec<&'a str>,
+    #[serde(default)]
+    pub Save: Vec<&'a str>,
+    #[serde(default)]
+    pub Load: Vec<&'a str>,
+    #[serde(default)]
+    pub RemapKeys: Vec<&'a str>,
+    #[serde(default)]
+    pub NextLanguage: Vec<&'a str>,
+    #[serde(default)]
+    pub ToggleEdgePanning: Vec<&'a str>,
+}
+
+// Converts keybindings into a map from inputs to the events that they trigger
+pub fn keybindings_to_map<P: Platform + ?Sized>(
+    bindings: Keybindings,
+) -> std::collections::HashMap<P::InputType, Event<P::MouseDistance>> {
+    let mut ret = std::collections::HashMap::new();
+    P::add_bindings(&mut ret, bindings.Right, Event::Right);
+    P::add_bindings(&mut ret, bindings.Left, Event::Left);
+    P::add_bindings(&mut ret, bindings.Up, Event::Up);
+    P::add_bindings(&mut ret, bindings.Down, Event::Down);
+    P::add_bindings(&mut ret, bindings.ZoomIn, Event::ZoomIn);
+    P::add_bindings(&mut ret, bindings.ZoomOut, Event::ZoomOut);
+    P::add_bindings(&mut ret, bindings.Select, Event::Select);
+    P::add_bindings(&mut ret, bindings.Cancel, Event::Cancel);
+    P::add_bindings(&mut ret, bindings.EndTurn, Event::EndTurn);
+    P::add_bindings(&mut ret, bindings.Save, Event::Save);
+    P::add_bindings(&mut ret, bindings.Load, Event::Load);
+    P::add_bindings(&mut ret, bindings.RemapKeys, Event::RemapKeys);
+    P::add_bindings(&mut ret, bindings.NextLanguage, Event::NextLanguage);
+    P::add_bindings(
+        &mut ret,
+        bindings.ToggleEdgePanning,
+        Event::ToggleEdgePanning,
+    );
+    ret
 }
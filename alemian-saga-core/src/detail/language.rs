@@ -0,0 +1,276 @@
+use std::fmt::Write;
+
+use crate::*;
+use constants::*;
+use detail::*;
+
+// A string in a language, which may have different forms depending on a count
+enum Text {
+    Single(String),
+    Plural(std::collections::HashMap<String, String>),
+}
+
+// Value substituted into a placeholder in a localized string
+pub enum Arg<'s> {
+    Number(i64),
+    Text(&'s str),
+}
+
+impl From<i32> for Arg<'_> {
+    fn from(n: i32) -> Self {
+        Arg::Number(n.into())
+    }
+}
+
+impl From<u32> for Arg<'_> {
+    fn from(n: u32) -> Self {
+        Arg::Number(n.into())
+    }
+}
+
+impl<'s> From<&'s str> for Arg<'s> {
+    fn from(text: &'s str) -> Self {
+        Arg::Text(text)
+    }
+}
+
+impl std::fmt::Display for Arg<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arg::Number(n) => n.fmt(f),
+            Arg::Text(text) => text.fmt(f),
+        }
+    }
+}
+
+// Strings for the language that the game is currently displayed in
+pub struct Language {
+    pub id: String,
+    strings: std::collections::HashMap<String, Text>,
+    plural: serialization::PluralRule,
+}
+
+impl Language {
+    pub async fn load<P: Platform>(platform: &P, id: &str) -> Result<Self, utility::Error> {
+        let path = format!("{}/{}.lang", LANGUAGE_DIRECTORY, id);
+        let file = platform.get_file(path.as_str()).await?;
+        let pack = file_format::decode_language_pack(file.as_ref())?;
+        let strings = pack.strings.into_iter().map(|(k, v)| {
+            let text = match v {
+                serialization::Message::Text(text) => Text::Single(text.to_owned()),
+                serialization::Message::Plural(forms) => Text::Plural(
+                    forms
+                        .into_iter()
+                        .map(|(form, text)| (form.to_owned(), text.to_owned()))
+                        .collect(),
+                ),
+            };
+            (k.to_owned(), text)
+        });
+        Ok(Self {
+            id: id.to_owned(),
+            strings: strings.collect(),
+            plural: pack.plural,
+        })
+    }
+
+    // Looks up a string, falling back to the key itself if the language doesn't define it
+    pub fn get<'s>(&'s self, key: &'s str) -> &'s str {
+        self.template(key, None)
+    }
+
+    // Looks up a string and fills in its placeholders, which are written as {name}
+    // An argument named "count" selects between plural forms using the language's plural rule
+    pub fn format(&self, key: &str, args: &[(&str, Arg)]) -> String {
+        let count = args.iter().find_map(|(name, arg)| match arg {
+            Arg::Number(n) if *name == "count" => Some(*n),
+            _ => None,
+        });
+        interpolate(self.template(key, count), args)
+    }
+
+    fn template<'s>(&'s self, key: &'s str, count: Option<i64>) -> &'s str {
+        match self.strings.get(key) {
+            Some(Text::Single(text)) => text.as_str(),
+            Some(Text::Plural(forms)) => count
+                .and_then(|n| forms.get(&format!("={}", n)))
+                .or_else(|| count.and_then(|n| forms.get(plural_form(self.plural, n))))
+                .or_else(|| forms.get("other"))
+                .map_or(key, String::as_str),
+            None => key,
+        }
+    }
+}
+
+// Gets the plural form that a count uses when a string has no form for that exact count
+fn plural_form(rule: serialization::PluralRule, count: i64) -> &'static str {
+    match (rule, count) {
+        (serialization::PluralRule::One, 1) => "one",
+        (serialization::PluralRule::ZeroOne, 0 | 1) => "one",
+        _ => "other",
+    }
+}
+
+// Replaces {name} placeholders with their arguments, leaving unknown placeholders untouched
+fn interpolate(template: &str, args: &[(&str, Arg)]) -> String {
+    let mut ret = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        ret.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = match after.find('}') {
+            Some(end) => end,
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        };
+        let name = &after[..end];
+        match args.iter().find(|(n, _)| *n == name) {
+            Some((_, arg)) => {
+                let _ = write!(ret, "{}", arg);
+            }
+            None => ret.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &after[end + 1..];
+    }
+    ret.push_str(rest);
+    ret
+}
+
+// Gets the ids of the languages that the game can be displayed in
+pub async fn get_languages<P: Platform>(platform: &P) -> Result<Vec<String>, utility::Error> {
+    let path = format!("{}/{}", LANGUAGE_DIRECTORY, LANGUAGE_INDEX);
+    let file = platform.get_file(path.as_str()).await?;
+    let index = file_format::decode_language_index(file.as_ref())?;
+    Ok(index.languages.into_iter().map(str::to_owned).collect())
+}
+
+// Loads the language that the user last chose, or the default language if they haven't chosen one
+pub async fn load_startup_language<P: Platform>(
+    platform: &P,
+    default: &str,
+) -> Result<Language, utility::Error> {
+    if let Ok(file) = platform.get_user_file(LANGUAGE_FILE).await {
+        if let Ok(id) = std::str::from_utf8(file.as_ref()) {
+            match Language::load(platform, id.trim()).await {
+                Ok(language) => return Ok(language),
+                Err(e) => {
+                    P::log(format!("Error: Could not load language {}: {}", id, e.msg).as_str())
+                }
+            }
+        }
+    }
+    Language::load(platform, default).await
+}
+
+#[cfg(test)]
+impl Language {
+    // Creates a language from strings without plural forms, for testing code that displays text
+    pub fn with_strings(strings: &[(&str, &str)]) -> Self {
+        let strings = strings
+            .iter()
+            .map(|(k, v)| ((*k).to_owned(), Text::Single((*v).to_owned())));
+        Self {
+            id: "test".to_owned(),
+            strings: strings.collect(),
+            plural: serialization::PluralRule::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn language(plural: serialization::PluralRule) -> Language {
+        let forms = [
+            ("=0", "no turns"),
+            ("one", "{count} turn"),
+            ("other", "{count} turns"),
+        ];
+        let forms = forms
+            .iter()
+            .map(|(f, t)| ((*f).to_owned(), (*t).to_owned()));
+        let strings = vec![
+            (
+                "greeting".to_owned(),
+                Text::Single("Hello {name}".to_owned()),
+            ),
+            ("turns".to_owned(), Text::Plural(forms.collect())),
+        ];
+        Language {
+            id: "test".to_owned(),
+            strings: strings.into_iter().collect(),
+            plural,
+        }
+    }
+
+    fn turns(language: &Language, count: i32) -> String {
+        language.format("turns", &[("count", Arg::from(count))])
+    }
+
+    #[test]
+    fn placeholders_are_filled_in() {
+        let language = language(serialization::PluralRule::One);
+        let text = language.format("greeting", &[("name", Arg::from("Maylis"))]);
+        assert_eq!(text, "Hello Maylis");
+    }
+
+    #[test]
+    fn missing_placeholders_are_left_alone() {
+        let language = language(serialization::PluralRule::One);
+        assert_eq!(language.format("greeting", &[]), "Hello {name}");
+    }
+
+    #[test]
+    fn literal_braces_are_kept() {
+        let args = [("x", Arg::from(1))];
+        assert_eq!(
+            interpolate("{} and {{x}} and {x", &args),
+            "{} and {{x}} and {x"
+        );
+    }
+
+    #[test]
+    fn test_languages_hold_plain_strings() {
+        let language = Language::with_strings(&[("greeting", "Hi {name}")]);
+        let text = language.format("greeting", &[("name", Arg::from("Gerard"))]);
+        assert_eq!(text, "Hi Gerard");
+        assert_eq!(language.get("unknown"), "unknown");
+    }
+
+    #[test]
+    fn missing_strings_fall_back_to_their_key() {
+        let language = language(serialization::PluralRule::One);
+        assert_eq!(language.get("unknown"), "unknown");
+    }
+
+    #[test]
+    fn exact_counts_take_priority() {
+        let language = language(serialization::PluralRule::ZeroOne);
+        assert_eq!(turns(&language, 0), "no turns");
+    }
+
+    #[test]
+    fn plural_forms_follow_the_language_rule() {
+        let one = language(serialization::PluralRule::One);
+        assert_eq!(turns(&one, 1), "1 turn");
+        assert_eq!(turns(&one, 2), "2 turns");
+        assert_eq!(turns(&one, -1), "-1 turns");
+
+        let zero_one = language(serialization::PluralRule::ZeroOne);
+        assert_eq!(plural_form(serialization::PluralRule::ZeroOne, 0), "one");
+        assert_eq!(turns(&zero_one, 1), "1 turn");
+        assert_eq!(turns(&zero_one, 3), "3 turns");
+
+        let other = language(serialization::PluralRule::Other);
+        assert_eq!(turns(&other, 1), "1 turns");
+    }
+
+    #[test]
+    fn plural_strings_without_a_count_use_the_other_form() {
+        let language = language(serialization::PluralRule::One);
+        assert_eq!(language.get("turns"), "{count} turns");
+    }
+}
@@ -1,18 +1,34 @@
+mod campaign;
 mod constants;
+mod drag;
 mod file_wrapper;
 mod game;
 mod keybindings;
+mod language;
+mod objectives;
+mod phase;
 mod rectangle;
+mod remap;
+mod rng;
 mod run;
 mod tile;
+mod unit;
 mod utility;
 mod vector;
 
 use crate::serialization;
+pub use constants::KEYBINDINGS_FILE;
+use drag::Drag;
 pub use file_wrapper::FileWrapper;
 use game::Game;
-pub use keybindings::Keybindings;
+pub use keybindings::{keybindings_to_map, Keybindings};
+use language::{Arg, Language};
+use phase::Phase;
 pub use rectangle::Rectangle;
+use remap::{Action, KeyRemapper, ABORT_KEY};
+use rng::Rng;
 pub use run::run_internal;
-use serialization::MapDistance;
+use serialization::{MapDistance, Turns};
 use tile::Tile;
+use unit::{Unit, UnitId};
+pub use utility::get_unit_image_path;
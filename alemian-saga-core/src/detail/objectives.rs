@@ -0,0 +1,356 @@
+use crate::*;
+use constants::*;
+use detail::*;
+use serialization::{DefeatCondition, Faction, Objective};
+
+// Why a map was lost
+#[derive(Clone, Copy)]
+pub enum DefeatReason<'a> {
+    // Every player unit was defeated
+    Routed,
+    // A unit that had to survive was defeated
+    Fallen(&'a str),
+    TurnLimit,
+}
+
+// How a map ended
+#[derive(Clone, Copy)]
+pub enum Outcome<'a> {
+    Victory,
+    Defeat(DefeatReason<'a>),
+}
+
+fn is_alive(units: &[Unit], name: &str) -> bool {
+    units
+        .iter()
+        .any(|u| u.info.name == name && u.info.hp > ZERO_HP)
+}
+
+// Whether a faction had units on the map and all of them have been defeated
+fn is_routed(units: &[Unit], faction: Faction) -> bool {
+    let mut units = units.iter().filter(|u| u.faction == faction).peekable();
+    units.peek().is_some() && units.all(|u| u.info.hp <= ZERO_HP)
+}
+
+fn is_met(units: &[Unit], turn: Turns, objective: &Objective) -> bool {
+    match objective {
+        Objective::Rout => is_routed(units, Faction::Red),
+        Objective::Seize(pos) => units
+            .iter()
+            .any(|u| u.faction == Faction::Blue && u.info.hp > ZERO_HP && u.info.position == *pos),
+        Objective::Survive(turns) => turn > *turns,
+        Objective::DefeatBoss(name) => units
+            .iter()
+            .any(|u| u.info.name == *name && u.info.hp <= ZERO_HP),
+        Objective::Protect { unit, turns } => turn > *turns && is_alive(units, unit),
+    }
+}
+
+// Gets the names of the units that a map is lost without
+pub fn required_units<'m>(
+    victory: &[Objective<'m>],
+    defeat: &[DefeatCondition<'m>],
+) -> Vec<&'m str> {
+    let protected = victory.iter().filter_map(|o| match o {
+        Objective::Protect { unit, .. } => Some(*unit),
+        _ => None,
+    });
+    let lords = defeat.iter().filter_map(|c| match c {
+        DefeatCondition::LordDies(name) => Some(*name),
+        _ => None,
+    });
+    protected.chain(lords).collect()
+}
+
+// Checks whether a map has been won or lost
+// Defeat is checked first, so a map is lost if an action both wins and loses it
+pub fn evaluate<'m>(
+    units: &[Unit],
+    turn: Turns,
+    victory: &[Objective<'m>],
+    defeat: &[DefeatCondition<'m>],
+) -> Option<Outcome<'m>> {
+    if is_routed(units, Faction::Blue) {
+        return Some(Outcome::Defeat(DefeatReason::Routed));
+    }
+    for condition in defeat.iter() {
+        match condition {
+            DefeatCondition::LordDies(name) if !is_alive(units, name) => {
+                return Some(Outcome::Defeat(DefeatReason::Fallen(name)));
+            }
+            DefeatCondition::TurnLimit(turns) if turn > *turns => {
+                return Some(Outcome::Defeat(DefeatReason::TurnLimit));
+            }
+            _ => {}
+        }
+    }
+    for objective in victory.iter() {
+        if let Objective::Protect { unit, .. } = objective {
+            if !is_alive(units, unit) {
+                return Some(Outcome::Defeat(DefeatReason::Fallen(unit)));
+            }
+        }
+    }
+    let won = if victory.is_empty() {
+        is_met(units, turn, &Objective::Rout)
+    } else {
+        victory.iter().all(|o| is_met(units, turn, o))
+    };
+    if won {
+        Some(Outcome::Victory)
+    } else {
+        None
+    }
+}
+
+// Describes how a map ended, one line at a time, for the result screen
+pub fn describe(language: &Language, outcome: Outcome, victory: &[Objective]) -> Vec<String> {
+    let mut lines = Vec::new();
+    match outcome {
+        Outcome::Victory => {
+            lines.push(language.get("victory").to_owned());
+            let rout = [Objective::Rout];
+            let objectives = if victory.is_empty() { &rout } else { victory };
+            for objective in objectives.iter() {
+                lines.push(match objective {
+                    Objective::Rout => language.get("objective_rout").to_owned(),
+                    Objective::Seize(_) => language.get("objective_seize").to_owned(),
+                    Objective::Survive(turns) => {
+                        language.format("objective_survive", &[("count", Arg::from(turns.value))])
+                    }
+                    Objective::DefeatBoss(name) => {
+                        language.format("objective_boss", &[("unit", Arg::from(*name))])
+                    }
+                    Objective::Protect { unit, .. } => {
+                        language.format("objective_protect", &[("unit", Arg::from(*unit))])
+                    }
+                });
+            }
+        }
+        Outcome::Defeat(reason) => {
+            lines.push(language.get("defeat").to_owned());
+            lines.push(match reason {
+                DefeatReason::Routed => language.get("defeat_routed").to_owned(),
+                DefeatReason::Fallen(name) => {
+                    language.format("defeat_fallen", &[("unit", Arg::from(name))])
+                }
+                DefeatReason::TurnLimit => language.get("defeat_turn_limit").to_owned(),
+            });
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use numeric_types::{accuracy_pts, hp, level, map_dist, turns};
+
+    fn unit(name: &str, x: i32) -> serialization::Unit<'_> {
+        serialization::Unit {
+            name,
+            class: serialization::Class::Noble,
+            level: level(1),
+            max_hp: hp(10),
+            hp: hp(10),
+            attack: hp(5),
+            defense: hp(1),
+            accuracy: accuracy_pts(80),
+            evade: accuracy_pts(10),
+            critical: accuracy_pts(5),
+            movement: map_dist(3),
+            remaining_move: map_dist(3),
+            position: Vector {
+                x: map_dist(x),
+                y: ZERO_TILES,
+            },
+        }
+    }
+
+    // The state of a map where Maylis and Gerard fight Boss, starting on the first turn
+    struct Battle {
+        units: Vec<Unit<'static>>,
+        turn: Turns,
+    }
+
+    fn with_game<F: FnOnce(&mut Battle)>(check: F) {
+        let units = vec![
+            Unit::new(unit("Maylis", 0), Faction::Blue),
+            Unit::new(unit("Gerard", 1), Faction::Blue),
+            Unit::new(unit("Boss", 3), Faction::Red),
+        ];
+        check(&mut Battle {
+            units,
+            turn: turns(1),
+        });
+    }
+
+    fn defeat_unit(game: &mut Battle, name: &str) {
+        let u = game.units.iter_mut().find(|u| u.info.name == name);
+        u.expect("Unit is in the game").info.hp = ZERO_HP;
+    }
+
+    fn outcome(game: &Battle, victory: &[Objective], defeat: &[DefeatCondition]) -> String {
+        match evaluate(&game.units, game.turn, victory, defeat) {
+            None => "playing".to_owned(),
+            Some(Outcome::Victory) => "victory".to_owned(),
+            Some(Outcome::Defeat(DefeatReason::Routed)) => "routed".to_owned(),
+            Some(Outcome::Defeat(DefeatReason::Fallen(name))) => format!("{} fell", name),
+            Some(Outcome::Defeat(DefeatReason::TurnLimit)) => "turn limit".to_owned(),
+        }
+    }
+
+    #[test]
+    fn maps_without_objectives_are_won_by_routing_the_enemy() {
+        with_game(|game| {
+            assert_eq!(outcome(game, &[], &[]), "playing");
+            defeat_unit(game, "Boss");
+            assert_eq!(outcome(game, &[], &[]), "victory");
+        });
+    }
+
+    #[test]
+    fn rout_needs_every_enemy_defeated() {
+        with_game(|game| {
+            let victory = [Objective::Rout];
+            assert_eq!(outcome(game, &victory, &[]), "playing");
+            defeat_unit(game, "Boss");
+            assert_eq!(outcome(game, &victory, &[]), "victory");
+        });
+    }
+
+    #[test]
+    fn seize_needs_a_living_player_unit_on_the_tile() {
+        with_game(|game| {
+            let target = Vector {
+                x: map_dist(2),
+                y: ZERO_TILES,
+            };
+            let victory = [Objective::Seize(target)];
+            assert_eq!(outcome(game, &victory, &[]), "playing");
+            game.units[1].info.position = target;
+            assert_eq!(outcome(game, &victory, &[]), "victory");
+            defeat_unit(game, "Gerard");
+            assert_eq!(outcome(game, &victory, &[]), "playing");
+        });
+    }
+
+    #[test]
+    fn survive_is_met_once_the_last_turn_has_ended() {
+        with_game(|game| {
+            let victory = [Objective::Survive(turns(3))];
+            game.turn = turns(3);
+            assert_eq!(outcome(game, &victory, &[]), "playing");
+            game.turn = turns(4);
+            assert_eq!(outcome(game, &victory, &[]), "victory");
+        });
+    }
+
+    #[test]
+    fn defeat_boss_only_needs_the_boss() {
+        with_game(|game| {
+            let victory = [Objective::DefeatBoss("Boss")];
+            assert_eq!(outcome(game, &victory, &[]), "playing");
+            defeat_unit(game, "Boss");
+            assert_eq!(outcome(game, &victory, &[]), "victory");
+        });
+    }
+
+    #[test]
+    fn protect_needs_the_unit_alive_until_the_last_turn_has_ended() {
+        with_game(|game| {
+            let victory = [Objective::Protect {
+                unit: "Gerard",
+                turns: turns(2),
+            }];
+            game.turn = turns(2);
+            assert_eq!(outcome(game, &victory, &[]), "playing");
+            game.turn = turns(3);
+            assert_eq!(outcome(game, &victory, &[]), "victory");
+            defeat_unit(game, "Gerard");
+            assert_eq!(outcome(game, &victory, &[]), "Gerard fell");
+        });
+    }
+
+    #[test]
+    fn maps_are_lost_when_the_lord_dies() {
+        with_game(|game| {
+            let defeat = [DefeatCondition::LordDies("Maylis")];
+            assert_eq!(outcome(game, &[], &defeat), "playing");
+            defeat_unit(game, "Maylis");
+            assert_eq!(outcome(game, &[], &defeat), "Maylis fell");
+        });
+    }
+
+    #[test]
+    fn maps_are_lost_once_the_turn_limit_has_passed() {
+        with_game(|game| {
+            let defeat = [DefeatCondition::TurnLimit(turns(5))];
+            game.turn = turns(5);
+            assert_eq!(outcome(game, &[], &defeat), "playing");
+            game.turn = turns(6);
+            assert_eq!(outcome(game, &[], &defeat), "turn limit");
+        });
+    }
+
+    #[test]
+    fn maps_are_lost_when_every_player_unit_is_defeated() {
+        with_game(|game| {
+            defeat_unit(game, "Maylis");
+            defeat_unit(game, "Gerard");
+            assert_eq!(outcome(game, &[], &[]), "routed");
+        });
+    }
+
+    #[test]
+    fn defeat_is_checked_before_victory() {
+        with_game(|game| {
+            let victory = [Objective::DefeatBoss("Boss")];
+            let defeat = [DefeatCondition::LordDies("Maylis")];
+            defeat_unit(game, "Boss");
+            defeat_unit(game, "Maylis");
+            assert_eq!(outcome(game, &victory, &defeat), "Maylis fell");
+        });
+    }
+
+    #[test]
+    fn protected_units_and_lords_are_required() {
+        let victory = [
+            Objective::Rout,
+            Objective::Protect {
+                unit: "Gerard",
+                turns: turns(2),
+            },
+        ];
+        let defeat = [
+            DefeatCondition::TurnLimit(turns(9)),
+            DefeatCondition::LordDies("Maylis"),
+        ];
+        assert_eq!(required_units(&victory, &defeat), ["Gerard", "Maylis"]);
+    }
+
+    #[test]
+    fn results_describe_the_objectives_or_the_reason_for_defeat() {
+        let language = Language::with_strings(&[
+            ("victory", "Victory!"),
+            ("defeat", "Defeat"),
+            ("objective_survive", "Survived {count} turns"),
+            ("objective_boss", "Defeated {unit}"),
+            ("defeat_fallen", "{unit} fell"),
+        ]);
+        let victory = [Objective::Survive(turns(3)), Objective::DefeatBoss("Boss")];
+        assert_eq!(
+            describe(&language, Outcome::Victory, &victory),
+            ["Victory!", "Survived 3 turns", "Defeated Boss"]
+        );
+        assert_eq!(
+            describe(&language, Outcome::Victory, &[]),
+            ["Victory!", "objective_rout"]
+        );
+        let fallen = Outcome::Defeat(DefeatReason::Fallen("Gerard"));
+        assert_eq!(
+            describe(&language, fallen, &victory),
+            ["Defeat", "Gerard fell"]
+        );
+    }
+}
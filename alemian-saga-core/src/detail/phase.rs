@@ -0,0 +1,18 @@
+// Represents which side is currently allowed to act
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Player,
+    Enemy,
+    Ally,
+}
+
+impl Phase {
+    // Gets the phase that follows this one; the turn counter advances when this wraps around
+    pub fn next(self) -> Self {
+        match self {
+            Phase::Player => Phase::Enemy,
+            Phase::Enemy => Phase::Ally,
+            Phase::Ally => Phase::Player,
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::*;
+use detail::*;
+
+// Something that keys can be bound to, listed in the order that the remapping screen shows them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    ZoomIn,
+    ZoomOut,
+    Select,
+    Cancel,
+    EndTurn,
+    Save,
+    Load,
+    RemapKeys,
+    NextLanguage,
+    ToggleEdgePanning,
+}
+
+impl Action {
+    const FIRST: Action = Action::Up;
+
+    // Name of the action in keybinding files, also used to look up its name in language packs
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Up => "Up",
+            Action::Down => "Down",
+            Action::Left => "Left",
+            Action::Right => "Right",
+            Action::ZoomIn => "ZoomIn",
+            Action::ZoomOut => "ZoomOut",
+            Action::Select => "Select",
+            Action::Cancel => "Cancel",
+            Action::EndTurn => "EndTurn",
+            Action::Save => "Save",
+            Action::Load => "Load",
+            Action::RemapKeys => "RemapKeys",
+            Action::NextLanguage => "NextLanguage",
+            Action::ToggleEdgePanning => "ToggleEdgePanning",
+        }
+    }
+
+    // Gets the action after this one, wrapping around to the first action after the last
+    fn next(self) -> Action {
+        match self {
+            Action::Up => Action::Down,
+            Action::Down => Action::Left,
+            Action::Left => Action::Right,
+            Action::Right => Action::ZoomIn,
+            Action::ZoomIn => Action::ZoomOut,
+            Action::ZoomOut => Action::Select,
+            Action::Select => Action::Cancel,
+            Action::Cancel => Action::EndTurn,
+            Action::EndTurn => Action::Save,
+            Action::Save => Action::Load,
+            Action::Load => Action::RemapKeys,
+            Action::RemapKeys => Action::NextLanguage,
+            Action::NextLanguage => Action::ToggleEdgePanning,
+            Action::ToggleEdgePanning => Action::FIRST,
+        }
+    }
+
+    fn previous(self) -> Action {
+        let mut action = self;
+        while action.next() != self {
+            action = action.next();
+        }
+        action
+    }
+
+    fn keys<'k>(self, bindings: &'k Keybindings) -> &'k [&'k str] {
+        match self {
+            Action::Up => &bindings.Up,
+            Action::Down => &bindings.Down,
+            Action::Left => &bindings.Left,
+            Action::Right => &bindings.Right,
+            Action::ZoomIn => &bindings.ZoomIn,
+            Action::ZoomOut => &bindings.ZoomOut,
+            Action::Select => &bindings.Select,
+            Action::Cancel => &bindings.Cancel,
+            Action::EndTurn => &bindings.EndTurn,
+            Action::Save => &bindings.Save,
+            Action::Load => &bindings.Load,
+            Action::RemapKeys => &bindings.RemapKeys,
+            Action::NextLanguage => &bindings.NextLanguage,
+            Action::ToggleEdgePanning => &bindings.ToggleEdgePanning,
+        }
+    }
+}
+
+// Pressing this key while waiting for a new key leaves the action's keys unchanged, so it can't
+// be captured; Cancel always keeps it so that it can't be lost by rebinding Cancel
+pub const ABORT_KEY: &str = "Escape";
+
+// State of the key remapping screen, where one action at a time can be given a new key
+pub struct KeyRemapper {
+    // Keys bound to each action, in the order that the screen lists the actions
+    keys: Vec<(Action, Vec<String>)>,
+    action: Action,
+    // Whether the next key pressed will be bound to the current action
+    pub capturing: bool,
+}
+
+impl KeyRemapper {
+    // Starts remapping the keybindings that are currently in use
+    pub fn new(bindings: &Keybindings) -> Self {
+        let mut keys = Vec::new();
+        let mut action = Action::FIRST;
+        loop {
+            let action_keys = action.keys(bindings).iter().map(|k| (*k).to_owned());
+            keys.push((action, action_keys.collect()));
+            action = action.next();
+            if action == Action::FIRST {
+                break;
+            }
+        }
+        Self {
+            keys,
+            action: Action::FIRST,
+            capturing: false,
+        }
+    }
+
+    // Gets the action that is currently chosen
+    pub fn current_action(&self) -> Action {
+        self.action
+    }
+
+    fn keys_of(&self, action: Action) -> &[String] {
+        let keys = self.keys.iter().find(|(a, _)| *a == action);
+        keys.map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    // Gets the keys that are bound to the current action
+    pub fn current_keys(&self) -> &[String] {
+        self.keys_of(self.action)
+    }
+
+    pub fn next_action(&mut self) {
+        self.action = self.action.next();
+    }
+
+    pub fn previous_action(&mut self) {
+        self.action = self.action.previous();
+    }
+
+    // Replaces the keys of the current action with a new key, leaving every other action alone
+    // Fails with the action that the key is already bound to if it's bound to a different one
+    pub fn bind(&mut self, key: String) -> Result<(), Action> {
+        let action = self.action;
+        let other = self
+            .keys
+            .iter()
+            .find(|(a, keys)| *a != action && keys.contains(&key));
+        if let Some((other, _)) = other {
+            return Err(*other);
+        }
+        let mut new_keys = vec![key];
+        if action == Action::Cancel && new_keys[0] != ABORT_KEY {
+            new_keys.push(ABORT_KEY.to_owned());
+        }
+        if let Some((_, keys)) = self.keys.iter_mut().find(|(a, _)| *a == action) {
+            *keys = new_keys;
+        }
+        Ok(())
+    }
+
+    pub fn keybindings(&self) -> Keybindings<'_> {
+        let key = |action: Action| self.keys_of(action).iter().map(String::as_str).collect();
+        Keybindings {
+            Up: key(Action::Up),
+            Down: key(Action::Down),
+            Left: key(Action::Left),
+            Right: key(Action::Right),
+            ZoomIn: key(Action::ZoomIn),
+            ZoomOut: key(Action::ZoomOut),
+            Select: key(Action::Select),
+            Cancel: key(Action::Cancel),
+            EndTurn: key(Action::EndTurn),
+            Save: key(Action::Save),
+            Load: key(Action::Load),
+            RemapKeys: key(Action::RemapKeys),
+            NextLanguage: key(Action::NextLanguage),
+            ToggleEdgePanning: key(Action::ToggleEdgePanning),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_bindings() -> Keybindings<'static> {
+        Keybindings {
+            Up: vec!["k", "ArrowUp"],
+            Down: vec!["j", "ArrowDown"],
+            Left: vec!["h"],
+            Right: vec!["l"],
+            ZoomIn: vec!["="],
+            ZoomOut: vec!["-"],
+            Select: vec![" ", "Enter"],
+            Cancel: vec!["Escape"],
+            EndTurn: vec!["e"],
+            Save: vec!["F6"],
+            Load: vec!["F9"],
+            RemapKeys: vec!["F2"],
+            NextLanguage: vec!["F3"],
+            ToggleEdgePanning: vec!["F4"],
+        }
+    }
+
+    #[test]
+    fn binding_one_action_keeps_the_keys_of_the_others() {
+        let mut remapper = KeyRemapper::new(&default_bindings());
+        remapper.bind("w".to_owned()).expect("w is not bound");
+        let bindings = remapper.keybindings();
+        assert_eq!(bindings.Up, vec!["w"]);
+        assert_eq!(bindings.Down, vec!["j", "ArrowDown"]);
+        assert_eq!(bindings.Select, vec![" ", "Enter"]);
+    }
+
+    #[test]
+    fn keys_bound_to_other_actions_are_rejected() {
+        let mut remapper = KeyRemapper::new(&default_bindings());
+        remapper.next_action();
+        assert_eq!(remapper.current_action(), Action::Down);
+        assert_eq!(remapper.bind("Enter".to_owned()), Err(Action::Select));
+        assert_eq!(remapper.current_keys(), ["j", "ArrowDown"]);
+    }
+
+    #[test]
+    fn an_action_can_keep_one_of_its_own_keys() {
+        let mut remapper = KeyRemapper::new(&default_bindings());
+        remapper.previous_action();
+        assert_eq!(remapper.current_action(), Action::ToggleEdgePanning);
+        assert_eq!(remapper.bind("F4".to_owned()), Ok(()));
+        assert_eq!(remapper.current_keys(), ["F4"]);
+    }
+
+    #[test]
+    fn every_action_is_listed_once() {
+        let remapper = KeyRemapper::new(&default_bindings());
+        assert_eq!(remapper.keys.len(), 14);
+        for (i, (action, _)) in remapper.keys.iter().enumerate() {
+            assert!(remapper.keys[..i].iter().all(|(a, _)| a != action));
+            assert_eq!(action.next().previous(), *action);
+        }
+    }
+
+    #[test]
+    fn cancel_keeps_the_abort_key() {
+        let mut remapper = KeyRemapper::new(&default_bindings());
+        while remapper.current_action() != Action::Cancel {
+            remapper.next_action();
+        }
+        assert_eq!(remapper.bind("Backspace".to_owned()), Ok(()));
+        assert_eq!(remapper.keybindings().Cancel, vec!["Backspace", ABORT_KEY]);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::*;
+use numeric_types::*;
+
+// Deterministic random number generator (SplitMix64)
+// The same seed always produces the same sequence of values, so that battles can be reproduced
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // Gets the internal state, which can be used as a seed to continue the same sequence
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Rolls a percentage in the range [0, 100)
+    pub fn roll(&mut self) -> AccuracyPoints {
+        accuracy_pts((self.next_u64() % 100) as i32)
+    }
+}
@@ -5,33 +5,326 @@ use constants::*;
 use detail::*;
 use numeric_types::*;
 
-// Main function containing all of the game logic
+// How a chapter came to an end
+enum ChapterEnd {
+    // Another chapter should be started, as described by an encoded Progress
+    Start(Vec<u8>),
+    // The player's saved game should be loaded, which may be on a different map
+    Load(Vec<u8>),
+    // There are no more events to handle
+    Quit,
+}
+
+// Everything needed to start playing a chapter
+struct ChapterStart<'c> {
+    chapter: usize,
+    roster: &'c serialization::Roster<'c>,
+    save: Option<&'c serialization::Save<'c>>,
+    // Whether to open the chapter select screen once the chapter is ready
+    chapter_select: bool,
+}
+
+// State of the chapter select screen
+enum ChapterSelect {
+    Open(usize),
+    Picked(usize),
+    Closed,
+}
+
+// Writes the current state of the game to the user's saved game file
+// Saved games don't record the phase, so the game can only be saved during the player phase
+async fn save_game<P: Platform>(
+    game: &Game<'_, '_, P>,
+    map: &str,
+    roster: &serialization::Roster<'_>,
+) -> Result<(), utility::Error> {
+    if game.phase != Phase::Player {
+        return Err(utility::Error::from(
+            "The game can only be saved during the player phase",
+        ));
+    }
+    let file = file_format::encode_save(&game.save(map, roster))?;
+    game.platform.put_user_file(SAVE_FILE, &file).await?;
+    Ok(())
+}
+
+// Reads the user's saved game file, making sure that it is for a chapter of the campaign
+async fn read_save<P: Platform>(
+    platform: &P,
+    campaign: &serialization::Campaign<'_>,
+) -> Result<Vec<u8>, utility::Error> {
+    let file = platform.get_user_file(SAVE_FILE).await?;
+    let save = file_format::decode_save(file.as_ref())?;
+    if campaign::find_chapter(campaign, save.map).is_none() {
+        return Err(utility::Error::from(
+            "Saved game is for a map that is not in the campaign",
+        ));
+    }
+    Ok(file.as_ref().to_vec())
+}
+
+// Records that a chapter has been won, carrying the surviving player units into the next one
+async fn complete_chapter<P: Platform>(
+    game: &Game<'_, '_, P>,
+    chapter: usize,
+    roster: &serialization::Roster<'_>,
+) -> Result<Vec<u8>, utility::Error> {
+    let progress = serialization::Progress {
+        chapter: chapter as u32 + 1,
+        roster: campaign::next_roster(roster, &game.units),
+    };
+    let file = file_format::encode_progress(&progress)?;
+    game.platform.put_user_file(PROGRESS_FILE, &file).await?;
+    Ok(file)
+}
+
+// Shows a panel of text until the player selects something to continue
+// Returns false if the event queue closed first
+async fn wait_for_select<P: Platform>(
+    game: &Game<'_, '_, P>,
+    event_queue: &mut futures::channel::mpsc::Receiver<Event<P::MouseDistance>>,
+    lines: &[String],
+) -> bool {
+    game.draw_panel(lines);
+    while let Some(e) = event_queue.next().await {
+        match e {
+            Event::Select | Event::MouseClick(_) | Event::Tap(_) => return true,
+            Event::Redraw => {
+                game.redraw();
+                game.draw_panel(lines);
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+// Handles an event while the chapter select screen is open
+fn choose_chapter<P: Platform>(
+    game: &Game<'_, '_, P>,
+    campaign: &serialization::Campaign<'_>,
+    chapter: usize,
+    event: Event<P::MouseDistance>,
+) -> ChapterSelect {
+    let count = campaign.chapters.len();
+    let chapter = match event {
+        Event::Up | Event::Left => (chapter + count - 1) % count,
+        Event::Down | Event::Right => (chapter + 1) % count,
+        Event::Select => return ChapterSelect::Picked(chapter),
+        Event::Cancel => {
+            game.redraw();
+            return ChapterSelect::Closed;
+        }
+        Event::Redraw => {
+            game.redraw();
+            chapter
+        }
+        _ => chapter,
+    };
+    let name = game.language.get(campaign.chapters[chapter].name);
+    let args = [
+        ("number", Arg::from(chapter as u32 + 1)),
+        ("chapter", Arg::from(name)),
+    ];
+    game.draw_prompt(game.language.format("chapter_select", &args).as_str());
+    ChapterSelect::Open(chapter)
+}
+
+// Writes remapped keybindings to the user's keybindings file and starts using them
+async fn save_keybindings<P: Platform>(
+    game: &Game<'_, '_, P>,
+    remapper: &KeyRemapper,
+) -> Result<(), utility::Error> {
+    let bindings = remapper.keybindings();
+    let file = serde_json::to_vec_pretty(&bindings)?;
+    game.platform.put_user_file(KEYBINDINGS_FILE, &file).await?;
+    game.platform
+        .set_keybindings(keybindings_to_map::<P>(bindings));
+    Ok(())
+}
+
+// Reads the keybindings that are currently in use and opens the key remapping screen for them
+async fn start_remap<P: Platform>(platform: &P) -> Result<KeyRemapper, utility::Error> {
+    let file = platform
+        .get_keybindings_file(platform.locale())
+        .await
+        .ok_or_else(|| utility::Error::from("No keybindings file was found"))?;
+    let bindings: Keybindings = serde_json::from_slice(file.as_ref())?;
+    Ok(KeyRemapper::new(&bindings))
+}
+
+// Draws the prompt for the key remapping screen
+// conflict holds a key that was rejected and the action that it's already bound to
+fn draw_remap_prompt<P: Platform>(
+    game: &Game<'_, '_, P>,
+    remapper: &KeyRemapper,
+    conflict: Option<(&str, Action)>,
+) {
+    let label = |key: &str| match key {
+        " " => game.language.get("key_space").to_owned(),
+        _ => key.to_owned(),
+    };
+    let action_key = format!("action_{}", remapper.current_action().name());
+    let action = game.language.get(action_key.as_str());
+    let prompt = match conflict {
+        Some((key, other)) => {
+            let other_key = format!("action_{}", other.name());
+            let key = label(key);
+            let args = [
+                ("key", Arg::from(key.as_str())),
+                ("other", Arg::from(game.language.get(other_key.as_str()))),
+                ("action", Arg::from(action)),
+            ];
+            game.language.format("remap_duplicate", &args)
+        }
+        None if remapper.capturing => {
+            let args = [("action", Arg::from(action))];
+            game.language.format("remap_prompt", &args)
+        }
+        None => {
+            let keys: Vec<_> = remapper.current_keys().iter().map(|k| label(k)).collect();
+            let keys = keys.join(", ");
+            let args = [
+                ("action", Arg::from(action)),
+                ("keys", Arg::from(keys.as_str())),
+            ];
+            game.language.format("remap_choose", &args)
+        }
+    };
+    game.draw_prompt(prompt.as_str());
+}
+
+// Handles an event while the key remapping screen is open
+// Returns the state of the screen, or None once the screen has been closed
+async fn remap_keys<P: Platform>(
+    game: &Game<'_, '_, P>,
+    mut remapper: KeyRemapper,
+    event: Event<P::MouseDistance>,
+) -> Option<KeyRemapper> {
+    let mut conflict = None;
+    if remapper.capturing {
+        match event {
+            Event::KeyCaptured => match game.platform.take_captured_key() {
+                Some(key) if key == ABORT_KEY => remapper.capturing = false,
+                Some(key) => match remapper.bind(key.clone()) {
+                    Ok(()) => {
+                        remapper.capturing = false;
+                        if let Err(e) = save_keybindings(game, &remapper).await {
+                            P::log(
+                                format!("Error: Could not save keybindings: {}", e.msg).as_str(),
+                            );
+                        }
+                    }
+                    Err(other) => conflict = Some((key, other)),
+                },
+                None => {}
+            },
+            Event::Redraw => game.redraw(),
+            _ => return Some(remapper),
+        }
+    } else {
+        match event {
+            Event::Up | Event::Left => remapper.previous_action(),
+            Event::Down | Event::Right => remapper.next_action(),
+            Event::Select => remapper.capturing = true,
+            Event::Cancel => {
+                game.redraw();
+                return None;
+            }
+            Event::Redraw => game.redraw(),
+            _ => return Some(remapper),
+        }
+    }
+    if remapper.capturing {
+        game.platform.capture_key();
+    }
+    let conflict = conflict.as_ref().map(|(key, other)| (key.as_str(), *other));
+    draw_remap_prompt(game, &remapper, conflict);
+    Some(remapper)
+}
+
+// Switches to the next available language and remembers the choice for next time
+async fn next_language<P: Platform>(
+    game: &mut Game<'_, '_, P>,
+    languages: &[String],
+) -> Result<(), utility::Error> {
+    let current = languages.iter().position(|l| *l == game.language.id);
+    let next = current.map_or(0, |i| (i + 1) % languages.len());
+    let id = languages
+        .get(next)
+        .ok_or_else(|| utility::Error::from("No languages are available"))?;
+    game.language = Language::load(game.platform, id).await?;
+    game.redraw();
+    game.platform
+        .put_user_file(LANGUAGE_FILE, id.as_bytes())
+        .await?;
+    Ok(())
+}
+
+// Reads whether the user has turned edge panning on or off, falling back to the platform's default
+async fn read_edge_panning<P: Platform>(platform: &P) -> bool {
+    match platform.get_user_file(EDGE_PANNING_FILE).await {
+        Ok(file) => match std::str::from_utf8(file.as_ref()).map(str::trim) {
+            Ok("on") => true,
+            Ok("off") => false,
+            _ => platform.edge_panning(),
+        },
+        Err(_) => platform.edge_panning(),
+    }
+}
+
+// Turns edge panning on or off and remembers the choice for later sessions
+async fn toggle_edge_panning<P: Platform>(
+    game: &Game<'_, '_, P>,
+    edge_panning: &mut bool,
+) -> Result<(), utility::Error> {
+    *edge_panning = !*edge_panning;
+    let (setting, prompt) = if *edge_panning {
+        ("on", "edge_panning_on")
+    } else {
+        ("off", "edge_panning_off")
+    };
+    game.draw_prompt(game.language.get(prompt));
+    game.platform
+        .put_user_file(EDGE_PANNING_FILE, setting.as_bytes())
+        .await?;
+    Ok(())
+}
+
+// Plays a single chapter of the campaign until it is won or another chapter is started
 // We use collect to avoid lazy iterator evaluation so that asynchronous tasks can run in parallel
 // There is a purpose to it, but clippy doesn't realize that
 #[allow(clippy::needless_collect)]
-pub async fn run_internal<P: Platform>(
-    platform: P,
+async fn play_chapter<P: Platform>(
+    platform: &P,
     event_queue: &mut futures::channel::mpsc::Receiver<Event<P::MouseDistance>>,
-    language: &str,
-) -> Result<(), utility::Error> {
-    let last_mouse_pan = P::now();
-
+    language: Language,
+    languages: &[String],
+    campaign: &serialization::Campaign<'_>,
+    start: ChapterStart<'_>,
+) -> Result<(Language, ChapterEnd), utility::Error> {
     let error_tile = serialization::TileType {
         image: "",
-        name: "ERROR",
+        name: "error_tile",
         defense: ZERO_HP,
         evade: BASE_EVADE_BONUS,
         move_cost: ONE_TILE,
+        heal: ZERO_HP,
     };
 
     // Retrieve map file
-    let map_path = format!("{}/map.map", language);
+    let chapter = &campaign.chapters[start.chapter];
+    let map_id = chapter.map;
+    let map_path = format!("{}.map", map_id);
     let map_file_future = platform.get_file(map_path.as_str());
     let cursor_future = P::get_image(constants::CURSOR_IMAGE);
     let info_future = P::get_image(constants::INFO_BAR_IMAGE);
     let unit_info_future = P::get_image(constants::UNIT_INFO_BAR_IMAGE);
+    let done_future = P::get_image(constants::DONE_IMAGE);
     let raw_map_file = map_file_future.await?;
-    let map_file: serialization::Map = rmp_serde::decode::from_read_ref(&raw_map_file)?;
+    let map_file = file_format::decode_map(raw_map_file.as_ref())?;
+    let required = objectives::required_units(&map_file.victory, &map_file.defeat);
+    let blue = campaign::deploy(&map_file.blue, start.roster, &required);
 
     // Create map from image paths to images
     let mut image_map = std::collections::HashMap::new();
@@ -40,11 +333,22 @@ pub async fn run_internal<P: Platform>(
         (image_str, P::get_image(image_str))
     });
     let tile_image_futures = images.collect::<Vec<_>>();
+    let factions = [
+        (serialization::Faction::Blue, &blue),
+        (serialization::Faction::Red, &map_file.red),
+        (serialization::Faction::Green, &map_file.green),
+    ];
     let mut unit_image_futures = std::collections::HashMap::new();
-    for u in map_file.blue.iter() {
-        unit_image_futures.entry(u.class).or_insert_with(|| {
-            P::get_image(format!("blue/{}.png", utility::get_class_name(u.class)).as_str())
-        });
+    for (faction, units) in factions.iter() {
+        for u in units.iter() {
+            unit_image_futures
+                .entry((*faction, u.class))
+                .or_insert_with(|| {
+                    let path = utility::get_unit_image_path(*faction, u.class);
+                    let future = P::get_image(path.as_str());
+                    (path, future)
+                });
+        }
     }
 
     for (n, f) in tile_image_futures.into_iter() {
@@ -53,13 +357,24 @@ pub async fn run_internal<P: Platform>(
         }
     }
 
+    let mut unit_images = std::collections::HashMap::new();
+    let mut unit_image_paths = std::collections::HashSet::new();
+    for (c, (path, f)) in unit_image_futures.into_iter() {
+        if let Some(image) = f.await {
+            unit_images.insert(c, image);
+            unit_image_paths.insert(path);
+        }
+    }
+
+    let image_exists = |path: &str| image_map.contains_key(path) || unit_image_paths.contains(path);
+    for problem in validation::validate_map(&map_file, image_exists) {
+        P::log(format!("Error: Invalid map file: {}", problem).as_str());
+    }
+
     // Generate the map
     let mut map = map_file.map.map(|i| {
         let tile = tile::get_tile::<P>(&image_map, &map_file.tile_types, *i as usize);
-        tile.unwrap_or_else(|| {
-            P::log("Error: Invalid map file");
-            tile::make_tile(None, &error_tile)
-        })
+        tile.unwrap_or_else(|| tile::make_tile(None, &error_tile))
     });
 
     let (rows, columns) = map.dim();
@@ -74,17 +389,23 @@ pub async fn run_internal<P: Platform>(
         cursor_future.await,
         info_future.await,
         unit_info_future.await,
-        last_mouse_pan,
+        map_file.seed,
+        language,
     );
 
-    for (c, f) in unit_image_futures.into_iter() {
-        if let Some(image) = f.await {
-            game.unit_images.insert(c, image);
+    game.unit_images = unit_images;
+    game.done_image = done_future.await;
+
+    for (faction, units) in factions.iter() {
+        for u in units.iter() {
+            game.add_unit(unit::Unit::new(u.clone(), *faction))?;
         }
     }
 
-    for u in map_file.blue.iter() {
-        game.add_unit(u);
+    if let Some(save) = start.save {
+        if let Err(e) = game.load(save) {
+            P::log(format!("Error: Could not load game: {}", e.msg).as_str());
+        }
     }
 
     game.redraw();
@@ -93,7 +414,40 @@ pub async fn run_internal<P: Platform>(
     let last_row = map_size.y - ONE_TILE;
     let mouse_pan_delay = P::nanoseconds(100000000);
 
+    let mut remapper = None;
+    let mut ignore_click = false;
+    let mut edge_panning = read_edge_panning(platform).await;
+    let mut chapter_select = None;
+    if start.chapter_select {
+        if let ChapterSelect::Open(c) =
+            choose_chapter(&game, campaign, start.chapter, Event::Redraw)
+        {
+            chapter_select = Some(c);
+        }
+    }
+
     while let Some(e) = event_queue.next().await {
+        if let Some(r) = remapper.take() {
+            remapper = remap_keys(&game, r, e).await;
+            continue;
+        }
+        if let Some(c) = chapter_select.take() {
+            match choose_chapter(&game, campaign, c, e) {
+                ChapterSelect::Open(c) => chapter_select = Some(c),
+                ChapterSelect::Picked(c) => {
+                    let progress = serialization::Progress {
+                        chapter: c as u32,
+                        roster: start.roster.clone(),
+                    };
+                    let file = file_format::encode_progress(&progress)?;
+                    return Ok((game.language, ChapterEnd::Start(file)));
+                }
+                ChapterSelect::Closed => {}
+            }
+            continue;
+        }
+        // Only the click that directly follows the end of a drag is ignored
+        let after_drag = std::mem::take(&mut ignore_click);
         match e {
             Event::Right => {
                 if game.cursor_pos.x < last_column {
@@ -151,51 +505,18 @@ pub async fn run_internal<P: Platform>(
                     }
                 }
             }
-            Event::ZoomIn => {
-                let tile_size = game.get_tile_size();
-                let size = &mut game.screen.size;
-                let cursor_pos_on_screen = game.cursor_pos - game.screen.top_left;
-                if tile_size.x >= tile_size.y && size.y > ONE_TILE {
-                    size.y -= ONE_TILE;
-                    if cursor_pos_on_screen.y > size.y / 2 {
-                        game.screen.top_left.y += ONE_TILE;
-                    }
-                }
-                if tile_size.y >= tile_size.x && size.x > ONE_TILE {
-                    size.x -= ONE_TILE;
-                    if cursor_pos_on_screen.x > size.x / 2 {
-                        game.screen.top_left.x += ONE_TILE;
-                    }
-                }
-                game.redraw();
-            }
-            Event::ZoomOut => {
-                let tile_size = game.get_tile_size();
-                let map_size = game.get_map_size();
-                let cursor_pos_on_screen = game.cursor_pos - game.screen.top_left;
-                let size = game.screen.size;
-                if size.y < map_size.y && (tile_size.y >= tile_size.x || size.x == map_size.x) {
-                    game.screen.size.y += ONE_TILE;
-                    if game.screen.bottom() > map_size.y
-                        || game.screen.top() > ZERO_TILES
-                            && cursor_pos_on_screen.y < game.screen.height() / 2
-                    {
-                        game.screen.top_left.y -= ONE_TILE;
-                    }
-                }
-                if size.x < map_size.x && (tile_size.x >= tile_size.y || size.y == map_size.y) {
-                    game.screen.size.x += ONE_TILE;
-                    if game.screen.right() > map_size.x
-                        || game.screen.left() > ZERO_TILES && cursor_pos_on_screen.x < size.x / 2
-                    {
-                        game.screen.top_left.x -= ONE_TILE;
-                    }
-                }
-                game.redraw();
-            }
+            Event::ZoomIn => game.zoom_in(None),
+            Event::ZoomOut => game.zoom_out(None),
+            Event::ZoomInAt(mouse_pos) => game.zoom_in(Some(mouse_pos)),
+            Event::ZoomOutAt(mouse_pos) => game.zoom_out(Some(mouse_pos)),
             Event::MouseMove(mouse_pos) => {
                 let time = P::now();
-                let pan = if P::duration_between(game.last_mouse_pan, time) > mouse_pan_delay {
+                let pan = if game.drag.is_some() {
+                    game.drag_to(mouse_pos);
+                    false
+                } else if edge_panning
+                    && P::duration_between(game.last_mouse_pan, time) > mouse_pan_delay
+                {
                     let screen_pos = mouse_pos.cast::<P::ScreenDistance>();
                     let half_tile_size = game.get_tile_size() / P::ScreenDistance::from(2);
                     let screen_size = game.platform.get_screen_size();
@@ -236,10 +557,178 @@ pub async fn run_internal<P: Platform>(
                     }
                 }
             }
+            Event::MouseDown(mouse_pos) => game.start_drag(mouse_pos),
+            Event::MouseUp(_) => ignore_click = game.end_drag(),
+            Event::MouseClick(mouse_pos) => {
+                // Releasing the mouse after dragging the map shouldn't select anything
+                if after_drag {
+                    continue;
+                }
+                game.select_at(mouse_pos);
+            }
+            Event::RightClick => game.cancel(),
+            Event::TouchStart(touch_pos) => game.start_drag(touch_pos),
+            Event::TouchMove(touch_pos) => game.drag_to(touch_pos),
+            Event::TouchEnd => {
+                game.end_drag();
+            }
+            Event::Tap(touch_pos) => game.select_at(touch_pos),
             Event::Redraw => game.redraw(),
             Event::Select => {
                 game.select_tile();
             }
+            Event::Cancel => game.cancel(),
+            Event::EndTurn => game.end_turn(),
+            Event::Save => {
+                if let Err(e) = save_game(&game, map_id, start.roster).await {
+                    P::log(format!("Error: Could not save game: {}", e.msg).as_str());
+                }
+            }
+            Event::Load => match read_save(platform, campaign).await {
+                Ok(file) => return Ok((game.language, ChapterEnd::Load(file))),
+                Err(e) => P::log(format!("Error: Could not load game: {}", e.msg).as_str()),
+            },
+            Event::RemapKeys => match start_remap(platform).await {
+                Ok(r) => remapper = remap_keys(&game, r, Event::Redraw).await,
+                Err(e) => P::log(format!("Error: Could not remap keys: {}", e.msg).as_str()),
+            },
+            Event::KeyCaptured => {}
+            Event::NextLanguage => {
+                if let Err(e) = next_language(&mut game, languages).await {
+                    P::log(format!("Error: Could not change language: {}", e.msg).as_str());
+                }
+            }
+            Event::ToggleEdgePanning => {
+                if let Err(e) = toggle_edge_panning(&game, &mut edge_panning).await {
+                    P::log(format!("Error: Could not save edge panning: {}", e.msg).as_str());
+                }
+            }
+        }
+
+        if let Some(outcome) =
+            objectives::evaluate(&game.units, game.turn, &map_file.victory, &map_file.defeat)
+        {
+            let (file, prompt) = match outcome {
+                objectives::Outcome::Victory => {
+                    let file = complete_chapter(&game, start.chapter, start.roster).await?;
+                    let prompt = if start.chapter + 1 < campaign.chapters.len() {
+                        "result_continue"
+                    } else {
+                        "campaign_complete"
+                    };
+                    (file, prompt)
+                }
+                // Losing a chapter starts it over with the units that it was started with
+                objectives::Outcome::Defeat(_) => {
+                    let progress = serialization::Progress {
+                        chapter: start.chapter as u32,
+                        roster: start.roster.clone(),
+                    };
+                    (file_format::encode_progress(&progress)?, "result_retry")
+                }
+            };
+            let mut lines = vec![game.language.get(chapter.name).to_owned()];
+            lines.extend(objectives::describe(
+                &game.language,
+                outcome,
+                &map_file.victory,
+            ));
+            let turns = [("count", Arg::from(game.turn.value))];
+            lines.push(game.language.format("turns_taken", &turns));
+            lines.push(game.language.get(prompt).to_owned());
+            let end = if wait_for_select(&game, event_queue, &lines).await {
+                ChapterEnd::Start(file)
+            } else {
+                ChapterEnd::Quit
+            };
+            return Ok((game.language, end));
+        }
+    }
+
+    Ok((game.language, ChapterEnd::Quit))
+}
+
+// Main function containing all of the game logic
+pub async fn run_internal<P: Platform>(
+    platform: P,
+    event_queue: &mut futures::channel::mpsc::Receiver<Event<P::MouseDistance>>,
+    language: &str,
+) -> Result<(), utility::Error> {
+    let mut language = language::load_startup_language(&platform, language).await?;
+    let languages = language::get_languages(&platform)
+        .await
+        .unwrap_or_else(|_| vec![language.id.clone()]);
+
+    // Games without a campaign file are played on the default map alone
+    let campaign_file = platform.get_file(CAMPAIGN_FILE).await.ok();
+    let campaign = match &campaign_file {
+        Some(file) => file_format::decode_campaign(file.as_ref())?,
+        None => campaign::default_campaign(),
+    };
+    if campaign.chapters.is_empty() {
+        return Err(utility::Error::from("The campaign has no chapters"));
+    }
+
+    let mut progress_file = platform
+        .get_user_file(PROGRESS_FILE)
+        .await
+        .ok()
+        .map(|f| f.as_ref().to_vec());
+    let mut save_file: Option<Vec<u8>> = None;
+    let new_campaign = serialization::Progress::default();
+
+    // Development builds start on the chapter select screen so that any chapter can be tested
+    let mut chapter_select = cfg!(debug_assertions) && campaign.chapters.len() > 1;
+
+    loop {
+        let (new_language, end) = {
+            let progress = match progress_file.as_deref().map(file_format::decode_progress) {
+                Some(Ok(progress)) => Some(progress),
+                Some(Err(e)) => {
+                    P::log(format!("Error: Could not read campaign progress: {}", e).as_str());
+                    None
+                }
+                None => None,
+            };
+            let save = save_file
+                .as_deref()
+                .map(file_format::decode_save)
+                .transpose()?;
+            let (chapter, roster) = match (&save, &progress) {
+                (Some(save), _) => (
+                    campaign::find_chapter(&campaign, save.map).unwrap_or_default(),
+                    &save.roster,
+                ),
+                // Once the last chapter is won, the campaign starts over
+                (None, Some(p)) if (p.chapter as usize) < campaign.chapters.len() => {
+                    (p.chapter as usize, &p.roster)
+                }
+                _ => (0, &new_campaign.roster),
+            };
+            let start = ChapterStart {
+                chapter,
+                roster,
+                save: save.as_ref(),
+                chapter_select: std::mem::take(&mut chapter_select),
+            };
+            play_chapter(
+                &platform,
+                event_queue,
+                language,
+                &languages,
+                &campaign,
+                start,
+            )
+            .await?
+        };
+        language = new_language;
+        match end {
+            ChapterEnd::Start(file) => {
+                progress_file = Some(file);
+                save_file = None;
+            }
+            ChapterEnd::Load(file) => save_file = Some(file),
+            ChapterEnd::Quit => break,
         }
     }
     P::log("closing");
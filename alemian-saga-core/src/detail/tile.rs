@@ -1,12 +1,14 @@
 use crate::*;
 use detail::constants::UNREACHABLE;
+use detail::UnitId;
 
 // Represents a tile in the map
 pub struct Tile<'a, P: Platform> {
     pub image: Option<&'a P::Image>,
     pub info: &'a serialization::TileType<'a>,
-    pub unit: std::cell::Cell<Option<&'a serialization::Unit<'a>>>,
+    pub unit: std::cell::Cell<Option<UnitId>>,
     pub remaining_move: std::cell::Cell<numeric_types::MapDistance>,
+    pub previous: std::cell::Cell<Option<Vector<numeric_types::MapDistance>>>,
 }
 
 pub fn make_tile<'a, P: Platform>(
@@ -18,6 +20,7 @@ pub fn make_tile<'a, P: Platform>(
         info,
         unit: std::cell::Cell::new(None),
         remaining_move: std::cell::Cell::new(UNREACHABLE),
+        previous: std::cell::Cell::new(None),
     }
 }
 
@@ -0,0 +1,56 @@
+use crate::*;
+use detail::*;
+
+// Index of a unit in the game's unit roster
+pub type UnitId = usize;
+
+// Represents the state of a unit during gameplay
+pub struct Unit<'a> {
+    pub info: serialization::Unit<'a>,
+    pub faction: serialization::Faction,
+    pub done: bool,
+}
+
+impl<'a> Unit<'a> {
+    pub fn new(info: serialization::Unit<'a>, faction: serialization::Faction) -> Self {
+        Self {
+            info,
+            faction,
+            done: false,
+        }
+    }
+
+    // Whether this unit acts during the given phase
+    pub fn acts_in(&self, phase: Phase) -> bool {
+        let acting_faction = match phase {
+            Phase::Player => serialization::Faction::Blue,
+            Phase::Enemy => serialization::Faction::Red,
+            Phase::Ally => serialization::Faction::Green,
+        };
+        self.faction == acting_faction
+    }
+
+    // Whether this unit fights on the same side as another unit
+    pub fn is_allied_with(&self, other: &Unit) -> bool {
+        // Blue and green units fight together against red units
+        let is_red = |u: &Unit| u.faction == serialization::Faction::Red;
+        is_red(self) == is_red(other)
+    }
+
+    // Gets the combat statistics of this unit when standing on a particular tile
+    pub fn combatant(&self, tile: &serialization::TileType) -> combat::Combatant {
+        combat::Combatant::new(&self.info, tile)
+    }
+
+    // Restores hit points, without going over the unit's maximum
+    pub fn heal(&mut self, amount: numeric_types::HitPoints) {
+        let healed = self.info.hp + amount;
+        self.info.hp = utility::partial_ord_min(healed, self.info.max_hp);
+    }
+
+    // Prepares the unit to act again at the start of its side's phase
+    pub fn refresh(&mut self) {
+        self.info.remaining_move = self.info.movement;
+        self.done = false;
+    }
+}
@@ -30,8 +30,33 @@ pub fn partial_ord_min<T: std::cmp::PartialOrd>(a: T, b: T) -> T {
     }
 }
 
+pub fn partial_ord_max<T: std::cmp::PartialOrd>(a: T, b: T) -> T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
 pub fn get_class_name(class: serialization::Class) -> &'static str {
     match class {
         serialization::Class::Noble => "noble",
     }
 }
+
+pub fn get_faction_name(faction: serialization::Faction) -> &'static str {
+    match faction {
+        serialization::Faction::Blue => "blue",
+        serialization::Faction::Red => "red",
+        serialization::Faction::Green => "green",
+    }
+}
+
+// Gets the path of the image used to draw units of a particular class and faction
+pub fn get_unit_image_path(faction: serialization::Faction, class: serialization::Class) -> String {
+    format!(
+        "{}/{}.png",
+        get_faction_name(faction),
+        get_class_name(class)
+    )
+}
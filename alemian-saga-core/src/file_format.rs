@@ -0,0 +1,685 @@
+use crate::*;
+use serialization::*;
+
+// Names that identify what kind of data a file contains
+pub const MAP_FORMAT: &str = "alemian-saga-map";
+pub const SAVE_FORMAT: &str = "alemian-saga-save";
+pub const PROGRESS_FORMAT: &str = "alemian-saga-progress";
+pub const CAMPAIGN_FORMAT: &str = "alemian-saga-campaign";
+pub const LANGUAGE_FORMAT: &str = "alemian-saga-language";
+pub const LANGUAGE_INDEX_FORMAT: &str = "alemian-saga-language-index";
+
+// Versions of each format written by this version of the game
+pub const MAP_VERSION: u32 = 3;
+pub const SAVE_VERSION: u32 = 3;
+pub const PROGRESS_VERSION: u32 = 1;
+pub const CAMPAIGN_VERSION: u32 = 1;
+pub const LANGUAGE_VERSION: u32 = 1;
+pub const LANGUAGE_INDEX_VERSION: u32 = 1;
+
+// Written at the start of every file that the game reads or writes
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Header<'a> {
+    pub format: &'a str,
+    pub version: u32,
+}
+
+// Reasons that a file could not be read or written
+#[derive(Debug)]
+pub enum Error {
+    Decode(rmp_serde::decode::Error),
+    Encode(rmp_serde::encode::Error),
+    WrongFormat {
+        expected: &'static str,
+        found: String,
+    },
+    TooNew {
+        format: &'static str,
+        version: u32,
+    },
+    Unsupported {
+        format: &'static str,
+        version: u32,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Decode(e) => e.fmt(f),
+            Error::Encode(e) => e.fmt(f),
+            Error::WrongFormat { expected, found } => {
+                write!(f, "Expected a {} file but found a {} file", expected, found)
+            }
+            Error::TooNew { format, version } => write!(
+                f,
+                "{} version {} is newer than this version of the game supports (version {})",
+                format,
+                version,
+                supported_version(format)
+            ),
+            Error::Unsupported { format, version } => {
+                write!(f, "{} version {} is not supported", format, version)
+            }
+        }
+    }
+}
+
+impl From<rmp_serde::decode::Error> for Error {
+    fn from(err: rmp_serde::decode::Error) -> Self {
+        Error::Decode(err)
+    }
+}
+
+impl From<rmp_serde::encode::Error> for Error {
+    fn from(err: rmp_serde::encode::Error) -> Self {
+        Error::Encode(err)
+    }
+}
+
+fn supported_version(format: &str) -> u32 {
+    match format {
+        SAVE_FORMAT => SAVE_VERSION,
+        PROGRESS_FORMAT => PROGRESS_VERSION,
+        CAMPAIGN_FORMAT => CAMPAIGN_VERSION,
+        LANGUAGE_FORMAT => LANGUAGE_VERSION,
+        LANGUAGE_INDEX_FORMAT => LANGUAGE_INDEX_VERSION,
+        _ => MAP_VERSION,
+    }
+}
+
+// Layout of tile types in version 1 and 2 maps, from before tiles could heal
+#[derive(serde::Deserialize)]
+struct TileTypeV1<'a> {
+    image: &'a str,
+    name: &'a str,
+    defense: HitPoints,
+    evade: AccuracyPoints,
+    move_cost: MapDistance,
+}
+
+impl<'a> From<TileTypeV1<'a>> for TileType<'a> {
+    fn from(tile_type: TileTypeV1<'a>) -> Self {
+        TileType {
+            image: tile_type.image,
+            name: tile_type.name,
+            defense: tile_type.defense,
+            evade: tile_type.evade,
+            move_cost: tile_type.move_cost,
+            heal: hp(0),
+        }
+    }
+}
+
+// Layout of version 1 maps, which are also the maps written before headers were added
+// The earliest of those maps stop after the blue units
+#[derive(serde::Deserialize)]
+struct MapV1<'a> {
+    #[serde(borrow)]
+    tile_types: Vec<TileTypeV1<'a>>,
+    map: ndarray::Array2<u32>,
+    blue: Vec<Unit<'a>>,
+    #[serde(default)]
+    red: Vec<Unit<'a>>,
+    #[serde(default)]
+    green: Vec<Unit<'a>>,
+    #[serde(default)]
+    seed: u64,
+}
+
+impl<'a> From<MapV1<'a>> for MapV2<'a> {
+    // Maps without objectives are won by routing the enemy
+    fn from(map: MapV1<'a>) -> Self {
+        MapV2 {
+            tile_types: map.tile_types,
+            map: map.map,
+            blue: map.blue,
+            red: map.red,
+            green: map.green,
+            seed: map.seed,
+            victory: Vec::new(),
+            defeat: Vec::new(),
+        }
+    }
+}
+
+// Layout of version 2 maps, which added objectives
+#[derive(serde::Deserialize)]
+struct MapV2<'a> {
+    #[serde(borrow)]
+    tile_types: Vec<TileTypeV1<'a>>,
+    map: ndarray::Array2<u32>,
+    blue: Vec<Unit<'a>>,
+    red: Vec<Unit<'a>>,
+    green: Vec<Unit<'a>>,
+    seed: u64,
+    #[serde(borrow)]
+    victory: Vec<Objective<'a>>,
+    #[serde(borrow)]
+    defeat: Vec<DefeatCondition<'a>>,
+}
+
+impl<'a> From<MapV2<'a>> for Map<'a> {
+    fn from(map: MapV2<'a>) -> Self {
+        Map {
+            tile_types: map.tile_types.into_iter().map(TileType::from).collect(),
+            map: map.map,
+            blue: map.blue,
+            red: map.red,
+            green: map.green,
+            seed: map.seed,
+            victory: map.victory,
+            defeat: map.defeat,
+        }
+    }
+}
+
+// Layout of version 1 saved games, which had no header and stored their version in the save
+#[derive(serde::Deserialize)]
+struct SaveV1<'a> {
+    version: u32,
+    map: &'a str,
+    turn: Turns,
+    seed: u64,
+    units: Vec<SavedUnit>,
+}
+
+impl<'a> From<SaveV1<'a>> for SaveV2<'a> {
+    fn from(save: SaveV1<'a>) -> Self {
+        SaveV2 {
+            map: save.map,
+            turn: save.turn,
+            seed: save.seed,
+            units: save.units,
+        }
+    }
+}
+
+// Layout of version 2 saved games, from before units were carried between chapters
+#[derive(serde::Deserialize)]
+struct SaveV2<'a> {
+    map: &'a str,
+    turn: Turns,
+    seed: u64,
+    units: Vec<SavedUnit>,
+}
+
+impl<'a> From<SaveV2<'a>> for Save<'a> {
+    fn from(save: SaveV2<'a>) -> Self {
+        Save {
+            map: save.map,
+            turn: save.turn,
+            seed: save.seed,
+            units: save.units,
+            roster: Roster::default(),
+        }
+    }
+}
+
+// Gets the header of a file, or None if the file was written before headers were added
+fn read_header(bytes: &[u8]) -> Option<Header<'_>> {
+    let (header, _): (Header, serde::de::IgnoredAny) =
+        rmp_serde::decode::from_read_ref(bytes).ok()?;
+    Some(header)
+}
+
+// Gets the version of a file after making sure that it's the expected kind of file
+fn check_header(header: &Header, format: &'static str) -> Result<u32, Error> {
+    if header.format != format {
+        Err(Error::WrongFormat {
+            expected: format,
+            found: header.format.to_owned(),
+        })
+    } else if header.version > supported_version(format) {
+        Err(Error::TooNew {
+            format,
+            version: header.version,
+        })
+    } else {
+        Ok(header.version)
+    }
+}
+
+fn encode<T: serde::Serialize>(format: &str, version: u32, body: &T) -> Result<Vec<u8>, Error> {
+    Ok(rmp_serde::encode::to_vec(&(
+        Header { format, version },
+        body,
+    ))?)
+}
+
+fn decode_body<'a, T: serde::Deserialize<'a>>(bytes: &'a [u8]) -> Result<T, Error> {
+    let (_, body): (serde::de::IgnoredAny, T) = rmp_serde::decode::from_read_ref(bytes)?;
+    Ok(body)
+}
+
+pub fn encode_map(map: &Map) -> Result<Vec<u8>, Error> {
+    encode(MAP_FORMAT, MAP_VERSION, map)
+}
+
+// Reads a map file, upgrading it if it was written in an older version of the format
+pub fn decode_map(bytes: &[u8]) -> Result<Map<'_>, Error> {
+    match read_header(bytes) {
+        None => {
+            let map: MapV1 = rmp_serde::decode::from_read_ref(bytes)?;
+            Ok(MapV2::from(map).into())
+        }
+        Some(header) => match check_header(&header, MAP_FORMAT)? {
+            1 => decode_body::<MapV1>(bytes).map(|map| MapV2::from(map).into()),
+            2 => decode_body::<MapV2>(bytes).map(Map::from),
+            3 => decode_body(bytes),
+            version => Err(Error::Unsupported {
+                format: MAP_FORMAT,
+                version,
+            }),
+        },
+    }
+}
+
+pub fn encode_save(save: &Save) -> Result<Vec<u8>, Error> {
+    encode(SAVE_FORMAT, SAVE_VERSION, save)
+}
+
+// Reads a saved game file, upgrading it if it was written in an older version of the format
+pub fn decode_save(bytes: &[u8]) -> Result<Save<'_>, Error> {
+    match read_header(bytes) {
+        None => {
+            let save: SaveV1 = rmp_serde::decode::from_read_ref(bytes)?;
+            if save.version == 1 {
+                Ok(SaveV2::from(save).into())
+            } else {
+                Err(Error::Unsupported {
+                    format: SAVE_FORMAT,
+                    version: save.version,
+                })
+            }
+        }
+        Some(header) => match check_header(&header, SAVE_FORMAT)? {
+            2 => decode_body::<SaveV2>(bytes).map(Save::from),
+            3 => decode_body(bytes),
+            version => Err(Error::Unsupported {
+                format: SAVE_FORMAT,
+                version,
+            }),
+        },
+    }
+}
+
+pub fn encode_progress(progress: &Progress) -> Result<Vec<u8>, Error> {
+    encode(PROGRESS_FORMAT, PROGRESS_VERSION, progress)
+}
+
+pub fn decode_progress(bytes: &[u8]) -> Result<Progress<'_>, Error> {
+    let header = read_header(bytes).ok_or(Error::WrongFormat {
+        expected: PROGRESS_FORMAT,
+        found: "headerless".to_owned(),
+    })?;
+    match check_header(&header, PROGRESS_FORMAT)? {
+        1 => decode_body(bytes),
+        version => Err(Error::Unsupported {
+            format: PROGRESS_FORMAT,
+            version,
+        }),
+    }
+}
+
+pub fn encode_campaign(campaign: &Campaign) -> Result<Vec<u8>, Error> {
+    encode(CAMPAIGN_FORMAT, CAMPAIGN_VERSION, campaign)
+}
+
+pub fn decode_campaign(bytes: &[u8]) -> Result<Campaign<'_>, Error> {
+    match read_header(bytes) {
+        // Campaigns written before headers were added have the same layout as version 1
+        None => Ok(rmp_serde::decode::from_read_ref(bytes)?),
+        Some(header) => match check_header(&header, CAMPAIGN_FORMAT)? {
+            1 => decode_body(bytes),
+            version => Err(Error::Unsupported {
+                format: CAMPAIGN_FORMAT,
+                version,
+            }),
+        },
+    }
+}
+
+pub fn encode_language_pack(pack: &LanguagePack) -> Result<Vec<u8>, Error> {
+    encode(LANGUAGE_FORMAT, LANGUAGE_VERSION, pack)
+}
+
+pub fn decode_language_pack(bytes: &[u8]) -> Result<LanguagePack<'_>, Error> {
+    match read_header(bytes) {
+        // Language packs written before headers were added have the same layout as version 1
+        None => Ok(rmp_serde::decode::from_read_ref(bytes)?),
+        Some(header) => match check_header(&header, LANGUAGE_FORMAT)? {
+            1 => decode_body(bytes),
+            version => Err(Error::Unsupported {
+                format: LANGUAGE_FORMAT,
+                version,
+            }),
+        },
+    }
+}
+
+pub fn encode_language_index(index: &LanguageIndex) -> Result<Vec<u8>, Error> {
+    encode(LANGUAGE_INDEX_FORMAT, LANGUAGE_INDEX_VERSION, index)
+}
+
+pub fn decode_language_index(bytes: &[u8]) -> Result<LanguageIndex<'_>, Error> {
+    match read_header(bytes) {
+        None => Ok(rmp_serde::decode::from_read_ref(bytes)?),
+        Some(header) => match check_header(&header, LANGUAGE_INDEX_FORMAT)? {
+            1 => decode_body(bytes),
+            version => Err(Error::Unsupported {
+                format: LANGUAGE_INDEX_FORMAT,
+                version,
+            }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use numeric_types::{
+        accuracy_pts, hp, level, map_dist, turns, AccuracyPoints, HitPoints, MapDistance,
+    };
+
+    // Tile types from before tiles could heal
+    type LegacyTileType<'a> = (&'a str, &'a str, HitPoints, AccuracyPoints, MapDistance);
+
+    const LEGACY_TILE: LegacyTileType = ("grass.png", "grass", hp(1), accuracy_pts(2), map_dist(1));
+
+    fn grid() -> ndarray::Array2<u32> {
+        ndarray::Array2::zeros((1, 2))
+    }
+
+    fn unit() -> Unit<'static> {
+        Unit {
+            name: "Maylis",
+            class: Class::Noble,
+            level: level(1),
+            max_hp: hp(10),
+            hp: hp(10),
+            attack: hp(5),
+            defense: hp(1),
+            accuracy: accuracy_pts(80),
+            evade: accuracy_pts(10),
+            critical: accuracy_pts(5),
+            movement: map_dist(3),
+            remaining_move: map_dist(3),
+            position: Vector {
+                x: map_dist(0),
+                y: map_dist(0),
+            },
+        }
+    }
+
+    fn save() -> Save<'static> {
+        Save {
+            map: "map",
+            turn: turns(4),
+            seed: 3,
+            units: Vec::new(),
+            roster: Roster {
+                units: Vec::new(),
+                fallen: vec!["Gerard"],
+            },
+        }
+    }
+
+    #[test]
+    fn maps_round_trip() {
+        let map = Map {
+            tile_types: vec![TileType {
+                image: "grass.png",
+                name: "grass",
+                defense: hp(1),
+                evade: accuracy_pts(2),
+                move_cost: map_dist(1),
+                heal: hp(3),
+            }],
+            map: grid(),
+            blue: Vec::new(),
+            red: Vec::new(),
+            green: Vec::new(),
+            seed: 9,
+            victory: vec![Objective::Survive(turns(5))],
+            defeat: vec![DefeatCondition::LordDies("Maylis")],
+        };
+        let bytes = encode_map(&map).expect("Failed to encode map");
+        let decoded = decode_map(&bytes).expect("Failed to decode map");
+        assert_eq!(decoded.tile_types[0].heal, hp(3));
+        assert_eq!(decoded.map, grid());
+        assert_eq!(decoded.seed, 9);
+        assert!(matches!(decoded.victory[..], [Objective::Survive(t)] if t == turns(5)));
+        assert!(matches!(
+            decoded.defeat[..],
+            [DefeatCondition::LordDies("Maylis")]
+        ));
+    }
+
+    #[test]
+    fn headerless_maps_are_read_as_version_1() {
+        let units: Vec<Unit> = Vec::new();
+        let legacy = (vec![LEGACY_TILE], grid(), &units, &units, &units, 9_u64);
+        let bytes = rmp_serde::encode::to_vec(&legacy).expect("Failed to encode map");
+        let map = decode_map(&bytes).expect("Failed to decode map");
+        assert_eq!(map.tile_types[0].name, "grass");
+        assert_eq!(map.tile_types[0].heal, hp(0));
+        assert_eq!(map.seed, 9);
+        assert!(map.victory.is_empty());
+        assert!(map.defeat.is_empty());
+    }
+
+    #[test]
+    fn the_earliest_headerless_maps_only_have_blue_units() {
+        let units = vec![unit()];
+        let legacy = (vec![LEGACY_TILE], grid(), &units);
+        let bytes = rmp_serde::encode::to_vec(&legacy).expect("Failed to encode map");
+        let map = decode_map(&bytes).expect("Failed to decode map");
+        assert_eq!(map.blue[0].name, "Maylis");
+        assert!(map.red.is_empty());
+        assert!(map.green.is_empty());
+        assert_eq!(map.seed, 0);
+    }
+
+    #[test]
+    fn version_1_maps_have_no_objectives() {
+        let units = vec![unit()];
+        let body = (vec![LEGACY_TILE], grid(), &units, &units, &units, 9_u64);
+        let bytes = encode(MAP_FORMAT, 1, &body).expect("Failed to encode map");
+        let map = decode_map(&bytes).expect("Failed to decode map");
+        assert_eq!(map.tile_types[0].heal, hp(0));
+        assert_eq!(map.red[0].name, "Maylis");
+        assert_eq!(map.seed, 9);
+        assert!(map.victory.is_empty());
+        assert!(map.defeat.is_empty());
+    }
+
+    #[test]
+    fn version_2_maps_have_tiles_without_healing() {
+        let units: Vec<Unit> = Vec::new();
+        let defeat: Vec<DefeatCondition> = Vec::new();
+        let body = (
+            vec![LEGACY_TILE],
+            grid(),
+            &units,
+            &units,
+            &units,
+            9_u64,
+            vec![Objective::Survive(turns(3))],
+            defeat,
+        );
+        let bytes = encode(MAP_FORMAT, 2, &body).expect("Failed to encode map");
+        let map = decode_map(&bytes).expect("Failed to decode map");
+        assert_eq!(map.tile_types[0].heal, hp(0));
+        assert!(matches!(map.victory[..], [Objective::Survive(t)] if t == turns(3)));
+    }
+
+    #[test]
+    fn headerless_saves_are_read_as_version_1() {
+        // [version 1, map "m", turn 2, seed 7, no units]
+        let bytes = [0x95, 0x01, 0xa1, b'm', 0x02, 0x07, 0x90];
+        let save = decode_save(&bytes).expect("Failed to decode save");
+        assert_eq!(save.map, "m");
+        assert_eq!(save.turn, turns(2));
+        assert_eq!(save.seed, 7);
+        assert!(save.roster.units.is_empty());
+        assert!(save.roster.fallen.is_empty());
+    }
+
+    #[test]
+    fn headerless_saves_with_other_versions_are_unsupported() {
+        let bytes = [0x95, 0x05, 0xa1, b'm', 0x02, 0x07, 0x90];
+        assert!(matches!(
+            decode_save(&bytes),
+            Err(Error::Unsupported {
+                format: SAVE_FORMAT,
+                version: 5
+            })
+        ));
+    }
+
+    #[test]
+    fn version_2_saves_start_with_an_empty_roster() {
+        let units: Vec<SavedUnit> = Vec::new();
+        let body = ("map", turns(4), 3_u64, units);
+        let bytes = encode(SAVE_FORMAT, 2, &body).expect("Failed to encode save");
+        let save = decode_save(&bytes).expect("Failed to decode save");
+        assert_eq!(save.map, "map");
+        assert_eq!(save.turn, turns(4));
+        assert!(save.roster.fallen.is_empty());
+    }
+
+    #[test]
+    fn saves_round_trip() {
+        let bytes = encode_save(&save()).expect("Failed to encode save");
+        let save = decode_save(&bytes).expect("Failed to decode save");
+        assert_eq!(save.turn, turns(4));
+        assert_eq!(save.roster.fallen, ["Gerard"]);
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let bytes = encode(SAVE_FORMAT, SAVE_VERSION + 1, &save()).expect("Failed to encode");
+        let e = decode_save(&bytes).err().expect("Save should be too new");
+        assert!(matches!(e, Error::TooNew { version, .. } if version == SAVE_VERSION + 1));
+        assert_eq!(
+            e.to_string(),
+            format!(
+                "alemian-saga-save version {} is newer than this version of the game supports \
+                 (version {})",
+                SAVE_VERSION + 1,
+                SAVE_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_older_versions_are_unsupported() {
+        let bytes = encode(MAP_FORMAT, 0, &()).expect("Failed to encode");
+        assert!(matches!(
+            decode_map(&bytes),
+            Err(Error::Unsupported {
+                format: MAP_FORMAT,
+                version: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn files_of_the_wrong_format_are_rejected() {
+        let bytes = encode_save(&save()).expect("Failed to encode save");
+        match decode_map(&bytes) {
+            Err(Error::WrongFormat { expected, found }) => {
+                assert_eq!(expected, MAP_FORMAT);
+                assert_eq!(found, SAVE_FORMAT);
+            }
+            _ => panic!("A save was read as a map"),
+        }
+    }
+
+    #[test]
+    fn progress_round_trips() {
+        let progress = Progress {
+            chapter: 2,
+            roster: save().roster,
+        };
+        let bytes = encode_progress(&progress).expect("Failed to encode progress");
+        let decoded = decode_progress(&bytes).expect("Failed to decode progress");
+        assert_eq!(decoded.chapter, 2);
+        assert_eq!(decoded.roster.fallen, ["Gerard"]);
+    }
+
+    #[test]
+    fn campaigns_round_trip() {
+        let campaign = Campaign {
+            chapters: vec![Chapter {
+                map: "map",
+                name: "chapter_1",
+            }],
+        };
+        let bytes = encode_campaign(&campaign).expect("Failed to encode campaign");
+        let decoded = decode_campaign(&bytes).expect("Failed to decode campaign");
+        assert_eq!(decoded.chapters[0].map, "map");
+        assert_eq!(decoded.chapters[0].name, "chapter_1");
+    }
+
+    #[test]
+    fn headerless_campaigns_are_read_as_version_1() {
+        let legacy = (vec![("map", "chapter_1"), ("map2", "chapter_2")],);
+        let bytes = rmp_serde::encode::to_vec(&legacy).expect("Failed to encode campaign");
+        let campaign = decode_campaign(&bytes).expect("Failed to decode campaign");
+        assert_eq!(campaign.chapters.len(), 2);
+        assert_eq!(campaign.chapters[1].map, "map2");
+    }
+
+    #[test]
+    fn progress_files_need_a_header() {
+        let bytes = rmp_serde::encode::to_vec(&Progress::default()).expect("Failed to encode");
+        assert!(matches!(
+            decode_progress(&bytes),
+            Err(Error::WrongFormat { found, .. }) if found == "headerless"
+        ));
+    }
+
+    #[test]
+    fn language_packs_round_trip() {
+        let pack = LanguagePack {
+            strings: vec![("victory", Message::Text("Victory!"))]
+                .into_iter()
+                .collect(),
+            plural: PluralRule::One,
+        };
+        let bytes = encode_language_pack(&pack).expect("Failed to encode language pack");
+        let decoded = decode_language_pack(&bytes).expect("Failed to decode language pack");
+        assert!(matches!(
+            decoded.strings["victory"],
+            Message::Text("Victory!")
+        ));
+        assert!(matches!(decoded.plural, PluralRule::One));
+    }
+
+    #[test]
+    fn headerless_language_files_are_read_as_version_1() {
+        let index = LanguageIndex {
+            languages: vec!["english", "spanish"],
+        };
+        let bytes = rmp_serde::encode::to_vec(&index).expect("Failed to encode index");
+        let decoded = decode_language_index(&bytes).expect("Failed to decode index");
+        assert_eq!(decoded.languages, ["english", "spanish"]);
+    }
+
+    #[test]
+    fn language_indexes_are_not_language_packs() {
+        let index = LanguageIndex {
+            languages: Vec::new(),
+        };
+        let bytes = encode_language_index(&index).expect("Failed to encode index");
+        assert!(matches!(
+            decode_language_pack(&bytes),
+            Err(Error::WrongFormat {
+                expected: LANGUAGE_FORMAT,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,272 @@
+use std::{cell, collections, path, rc};
+
+use async_trait::async_trait;
+use futures::task::LocalSpawnExt;
+
+use crate::*;
+
+thread_local! {
+    static CLOCK: cell::Cell<u64> = const { cell::Cell::new(0) };
+    static LOG: cell::RefCell<Vec<String>> = const { cell::RefCell::new(Vec::new()) };
+    static KEYS: cell::RefCell<collections::VecDeque<String>> =
+        const { cell::RefCell::new(collections::VecDeque::new()) };
+}
+
+// A call made to one of the drawing functions of the headless platform
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCall {
+    Image {
+        path: String,
+        left: f64,
+        top: f64,
+        width: f64,
+        height: f64,
+    },
+    Rectangle {
+        left: f64,
+        top: f64,
+        width: f64,
+        height: f64,
+    },
+    Text {
+        text: String,
+        x: f64,
+        y: f64,
+        max_width: f64,
+    },
+}
+
+// Platform that reads files from local directories and records drawing instead of displaying it
+// Intended for driving the game from automated tests
+pub struct Headless {
+    search_path: Vec<path::PathBuf>,
+    user_directory: path::PathBuf,
+    screen_size: Vector<f64>,
+    draw_calls: rc::Rc<cell::RefCell<Vec<DrawCall>>>,
+}
+
+impl Headless {
+    // Files are looked up in each directory of search_path in order
+    pub fn new(
+        search_path: Vec<path::PathBuf>,
+        user_directory: path::PathBuf,
+        screen_size: Vector<f64>,
+    ) -> Self {
+        Self {
+            search_path,
+            user_directory,
+            screen_size,
+            draw_calls: rc::Rc::new(cell::RefCell::new(Vec::new())),
+        }
+    }
+
+    // Gets a handle to the draw calls that the platform has recorded
+    // The handle stays valid after the platform has been moved into the game
+    pub fn draw_calls(&self) -> rc::Rc<cell::RefCell<Vec<DrawCall>>> {
+        self.draw_calls.clone()
+    }
+
+    // Advances the clock returned by now() on the current thread
+    pub fn advance_clock(ns: u64) {
+        CLOCK.with(|c| c.set(c.get() + ns));
+    }
+
+    // Removes and returns the messages logged on the current thread
+    pub fn take_log() -> Vec<String> {
+        LOG.with(|l| l.replace(Vec::new()))
+    }
+
+    // Queues the name of a key to be reported by take_captured_key
+    // Send Event::KeyCaptured afterwards to deliver it to the game
+    pub fn queue_key(key: &str) {
+        KEYS.with(|k| k.borrow_mut().push_back(key.to_owned()));
+    }
+
+    // Reads a file from the first directory in the search path that contains it
+    pub(crate) fn read_file(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.search_path
+            .iter()
+            .find_map(|d| std::fs::read(d.join(path)).ok())
+            .ok_or_else(|| format!("File {} does not exist", path))
+    }
+
+    fn record(&self, call: DrawCall) {
+        self.draw_calls.borrow_mut().push(call);
+    }
+}
+
+// A step in a script that drives the game
+#[derive(Clone, Copy)]
+pub enum Step {
+    // Sends an event to the game and waits for the game to finish handling it
+    Event(Event<i32>),
+    // Advances the clock by a number of nanoseconds before the next event
+    AdvanceClock(u64),
+}
+
+impl From<Event<i32>> for Step {
+    fn from(event: Event<i32>) -> Self {
+        Step::Event(event)
+    }
+}
+
+// Runs the game on a headless platform until every step in the script has been processed
+// Fails if the game stops before the script is over
+pub fn run_script<I: IntoIterator<Item = Step>>(
+    platform: Headless,
+    script: I,
+    language: &str,
+) -> Result<(), String> {
+    let mut session = Session::new(platform, language);
+    for step in script.into_iter() {
+        match step {
+            Step::Event(e) => session.send(e)?,
+            Step::AdvanceClock(ns) => Headless::advance_clock(ns),
+        }
+    }
+    session.finish();
+    Ok(())
+}
+
+// Runs the game one event at a time, so that its output can be inspected after each event
+pub struct Session {
+    pool: futures::executor::LocalPool,
+    events: futures::channel::mpsc::Sender<Event<i32>>,
+}
+
+impl Session {
+    // Starts the game and waits for it to finish loading
+    pub fn new<P: Platform<MouseDistance = i32> + 'static>(platform: P, language: &str) -> Self {
+        let (events, receiver) = futures::channel::mpsc::channel(1);
+        let language = language.to_owned();
+        let mut pool = futures::executor::LocalPool::new();
+        let game = async move { run(platform, receiver, language.as_str()).await };
+        // Spawning only fails if the pool has shut down, which it can't have yet
+        let _ = pool.spawner().spawn_local(game);
+        pool.run_until_stalled();
+        Self { pool, events }
+    }
+
+    // Sends an event to the game and waits for the game to finish handling it
+    // Fails if the game has stopped or hasn't taken the previous event from the queue
+    pub fn send(&mut self, event: Event<i32>) -> Result<(), String> {
+        self.events.try_send(event).map_err(|e| {
+            if e.is_disconnected() {
+                "The game has stopped".to_owned()
+            } else {
+                "The game is not waiting for another event".to_owned()
+            }
+        })?;
+        self.pool.run_until_stalled();
+        Ok(())
+    }
+
+    // Stops sending events and waits for the game to shut down
+    pub fn finish(self) {
+        let Self { mut pool, events } = self;
+        drop(events);
+        pool.run();
+    }
+}
+
+#[async_trait(?Send)]
+impl Platform for Headless {
+    type Error = String;
+
+    type Image = String;
+
+    type InputType = String;
+
+    type MouseDistance = i32;
+
+    type ScreenDistance = f64;
+
+    type ImageFuture = futures::future::Ready<Option<String>>;
+
+    type File = Vec<u8>;
+
+    type UserFile = Vec<u8>;
+
+    type Instant = u64;
+
+    type Duration = u64;
+
+    fn draw_primitive(&self, img: &String, left: f64, top: f64, width: f64, height: f64) {
+        self.record(DrawCall::Image {
+            path: img.clone(),
+            left,
+            top,
+            width,
+            height,
+        });
+    }
+
+    fn draw_rectangle(&self, left: f64, top: f64, width: f64, height: f64) {
+        self.record(DrawCall::Rectangle {
+            left,
+            top,
+            width,
+            height,
+        });
+    }
+
+    fn draw_text_primitive(&self, text: &str, x: f64, y: f64, max_width: f64) {
+        self.record(DrawCall::Text {
+            text: text.to_owned(),
+            x,
+            y,
+            max_width,
+        });
+    }
+
+    fn string_to_input(input: &str) -> String {
+        input.to_owned()
+    }
+
+    fn get_width(&self) -> f64 {
+        self.screen_size.x
+    }
+
+    fn get_height(&self) -> f64 {
+        self.screen_size.y
+    }
+
+    // Images are identified by their path, so every image is available
+    fn get_image(path: &str) -> Self::ImageFuture {
+        futures::future::ready(Some(path.to_owned()))
+    }
+
+    async fn get_file(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.read_file(path)
+    }
+
+    async fn get_user_file(&self, path: &str) -> Result<Vec<u8>, String> {
+        std::fs::read(self.user_directory.join(path)).map_err(|e| e.to_string())
+    }
+
+    async fn put_user_file(&self, path: &str, contents: &[u8]) -> Result<(), String> {
+        std::fs::create_dir_all(&self.user_directory).map_err(|e| e.to_string())?;
+        std::fs::write(self.user_directory.join(path), contents).map_err(|e| e.to_string())
+    }
+
+    fn log(msg: &str) {
+        LOG.with(|l| l.borrow_mut().push(msg.to_owned()));
+    }
+
+    fn take_captured_key(&self) -> Option<String> {
+        KEYS.with(|k| k.borrow_mut().pop_front())
+    }
+
+    fn now() -> u64 {
+        CLOCK.with(|c| c.get())
+    }
+
+    fn nanoseconds(ns: usize) -> u64 {
+        ns as u64
+    }
+
+    // The clock never goes backwards on its own, but the moments may come from different threads
+    fn duration_between(first: u64, second: u64) -> u64 {
+        second.saturating_sub(first)
+    }
+}
@@ -1,9 +1,16 @@
 #![cfg_attr(feature = "strict", deny(warnings))]
 #![feature(const_fn_trait_bound)]
 
+pub mod combat;
 mod detail;
+pub mod file_format;
+#[cfg(any(test, feature = "headless"))]
+pub mod headless;
 pub mod numeric_types;
 pub mod serialization;
+#[cfg(feature = "software")]
+pub mod software;
+pub mod validation;
 
 #[macro_use]
 extern crate uom;
@@ -105,6 +112,9 @@ pub trait Platform {
     // Retrieve a user specific file
     async fn get_user_file(&self, path: &str) -> Result<Self::UserFile, Self::Error>;
 
+    // Write a user specific file, replacing it if it already exists
+    async fn put_user_file(&self, path: &str, contents: &[u8]) -> Result<(), Self::Error>;
+
     // Log a message (typically to stdout or the equivalent)
     fn log(path: &str);
 
@@ -117,6 +127,11 @@ pub trait Platform {
     // Gets the amount of time between two moments
     fn duration_between(fist: Self::Instant, second: Self::Instant) -> Self::Duration;
 
+    // Whether the map should pan when the mouse is held near the edge of the screen
+    fn edge_panning(&self) -> bool {
+        true
+    }
+
     // Gets the size of the screen
     fn get_screen_size(&self) -> Vector<Self::ScreenDistance> {
         Vector {
@@ -155,29 +170,47 @@ pub trait Platform {
         }
     }
 
+    // Gets the locale whose keybindings are used until the user remaps their keys
+    fn locale(&self) -> &str {
+        "us"
+    }
+
+    // Reads the user's keybindings file, or the default keybindings for a locale if there isn't one
+    async fn get_keybindings_file(&self, locale: &str) -> Option<detail::FileWrapper<Self>> {
+        match self.get_user_file(detail::KEYBINDINGS_FILE).await {
+            Ok(f) => Some(detail::FileWrapper::User(f)),
+            _ => {
+                let keybindings_path = format!("keybindings/{}.json", locale);
+                let file = self.get_file(keybindings_path.as_str()).await.ok()?;
+                Some(detail::FileWrapper::Global(file))
+            }
+        }
+    }
+
     // Retrieves a keybinding map describing the what keys map to what actions
     async fn get_keybindings(
         &self,
         locale: &str,
     ) -> Option<std::collections::HashMap<Self::InputType, Event<Self::MouseDistance>>> {
-        let mut ret = std::collections::HashMap::new();
-        let user_file = self.get_user_file("keybindings.json").await;
-        let file: detail::FileWrapper<Self> = match user_file {
-            Ok(f) => detail::FileWrapper::User(f),
-            _ => {
-                let keybindings_path = format!("keybindings/{}.json", locale);
-                detail::FileWrapper::Global(self.get_file(keybindings_path.as_str()).await.ok()?)
-            }
-        };
+        let file = self.get_keybindings_file(locale).await?;
         let bindings: detail::Keybindings = serde_json::from_slice(file.as_ref()).ok()?;
-        Self::add_bindings(&mut ret, bindings.Right, Event::Right);
-        Self::add_bindings(&mut ret, bindings.Left, Event::Left);
-        Self::add_bindings(&mut ret, bindings.Up, Event::Up);
-        Self::add_bindings(&mut ret, bindings.Down, Event::Down);
-        Self::add_bindings(&mut ret, bindings.ZoomIn, Event::ZoomIn);
-        Self::add_bindings(&mut ret, bindings.ZoomOut, Event::ZoomOut);
-        Self::add_bindings(&mut ret, bindings.Select, Event::Select);
-        Some(ret)
+        Some(detail::keybindings_to_map::<Self>(bindings))
+    }
+
+    // Reports the next key that gets pressed as an Event::KeyCaptured instead of its bound event
+    // Platforms without a keyboard can leave this unimplemented
+    fn capture_key(&self) {}
+
+    // Gets the name of the key that was captured after calling capture_key
+    fn take_captured_key(&self) -> Option<String> {
+        None
+    }
+
+    // Replaces the keybinding map used to translate key presses into events
+    fn set_keybindings(
+        &self,
+        _bindings: std::collections::HashMap<Self::InputType, Event<Self::MouseDistance>>,
+    ) {
     }
 
     // Renders text to the screen
@@ -192,7 +225,7 @@ pub trait Platform {
 }
 
 // Represents a vector
-#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Vector<T> {
     pub x: T,
     pub y: T,
@@ -207,9 +240,27 @@ pub enum Event<P> {
     Down,
     ZoomIn,
     ZoomOut,
+    ZoomInAt(Vector<P>),
+    ZoomOutAt(Vector<P>),
     MouseMove(Vector<P>),
+    MouseDown(Vector<P>),
+    MouseUp(Vector<P>),
+    MouseClick(Vector<P>),
+    RightClick,
+    TouchStart(Vector<P>),
+    TouchMove(Vector<P>),
+    TouchEnd,
+    Tap(Vector<P>),
     Redraw,
     Select,
+    Cancel,
+    EndTurn,
+    Save,
+    Load,
+    RemapKeys,
+    KeyCaptured,
+    NextLanguage,
+    ToggleEdgePanning,
 }
 
 // Entry point for starting game logic
@@ -1,6 +1,6 @@
 quantity! {
     quantity: AccuracyPoints; "AccuracyPoints";
-    dimension: Q<Z0, Z0, P1, Z0>;
+    dimension: Q<Z0, Z0, P1, Z0, Z0>;
     units {
         @point: 1.0E0; "pt", "point", "points";
     }
@@ -1,6 +1,6 @@
 quantity! {
     quantity: HitPoints; "HitPoints";
-    dimension: Q<Z0, P1, Z0, Z0>;
+    dimension: Q<Z0, P1, Z0, Z0, Z0>;
     units {
         @point: 1.0E0; "hp", "point", "points";
     }
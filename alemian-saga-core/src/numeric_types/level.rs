@@ -1,6 +1,6 @@
 quantity! {
     quantity: Level; "Level";
-    dimension: Q<Z0, Z0, Z0, P1>;
+    dimension: Q<Z0, Z0, Z0, P1, Z0>;
     units {
         @level: 1.0E0; "lv", "level", "level";
     }
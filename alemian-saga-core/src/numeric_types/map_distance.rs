@@ -1,6 +1,6 @@
 quantity! {
     quantity: MapDistance; "MapDistance";
-    dimension: Q<P1, Z0, Z0, Z0>;
+    dimension: Q<P1, Z0, Z0, Z0, Z0>;
     units {
         @tile: 1.0E0; "t", "tile", "tiles";
     }
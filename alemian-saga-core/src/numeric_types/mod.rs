@@ -12,18 +12,23 @@ mod accuracy_points;
 #[macro_use]
 mod level;
 
+#[macro_use]
+mod turns;
+
 system! {
     quantities: Q {
         map_distance: tile, D;
         hit_points: point, H;
         accuracy_points: point, A;
         level: level, L;
+        turns: turn, T;
     }
     units: U {
         mod map_distance::MapDistance,
         mod hit_points::HitPoints,
         mod accuracy_points::AccuracyPoints,
         mod level::Level,
+        mod turns::Turns,
     }
 }
 
@@ -34,6 +39,7 @@ pub use self::i32::AccuracyPoints;
 pub use self::i32::HitPoints;
 pub use self::i32::MapDistance;
 pub use self::u32::Level;
+pub use self::u32::Turns;
 
 pub const fn map_dist(value: i32) -> MapDistance {
     MapDistance {
@@ -66,3 +72,11 @@ pub const fn level(value: u32) -> Level {
         value,
     }
 }
+
+pub const fn turns(value: u32) -> Turns {
+    Turns {
+        dimension: PhantomData,
+        units: PhantomData,
+        value,
+    }
+}
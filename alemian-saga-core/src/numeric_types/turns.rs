@@ -0,0 +1,7 @@
+quantity! {
+    quantity: Turns; "Turns";
+    dimension: Q<Z0, Z0, Z0, Z0, P1>;
+    units {
+        @turn: 1.0E0; "turn", "turn", "turns";
+    }
+}
@@ -5,10 +5,13 @@ pub use numeric_types::*;
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct TileType<'a> {
     pub image: &'a str,
+    // Key used to look up the name of the tile type in the language pack
     pub name: &'a str,
     pub defense: HitPoints,
     pub evade: AccuracyPoints,
     pub move_cost: MapDistance,
+    // Hit points restored to a unit standing on the tile at the start of its side's phase
+    pub heal: HitPoints,
 }
 
 // Serialized format for maps
@@ -18,6 +21,50 @@ pub struct Map<'a> {
     pub tile_types: Vec<TileType<'a>>,
     pub map: ndarray::Array2<u32>,
     pub blue: Vec<Unit<'a>>,
+    pub red: Vec<Unit<'a>>,
+    pub green: Vec<Unit<'a>>,
+    pub seed: u64,
+    // Everything in this list must be done to win the map; an empty list means routing the enemy
+    #[serde(borrow)]
+    pub victory: Vec<Objective<'a>>,
+    #[serde(borrow)]
+    pub defeat: Vec<DefeatCondition<'a>>,
+}
+
+// Something that the player must do to win a map
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub enum Objective<'a> {
+    // Defeat every enemy unit
+    Rout,
+    // Move a player unit onto a tile
+    Seize(Vector<MapDistance>),
+    // Hold out until a number of turns have ended
+    Survive(Turns),
+    // Defeat the enemy unit with a particular name
+    DefeatBoss(&'a str),
+    // Keep the unit with a particular name alive until a number of turns have ended
+    // The map is lost if the unit is defeated
+    Protect { unit: &'a str, turns: Turns },
+}
+
+// Something that makes the player lose a map
+// The map is also lost once every player unit has been defeated
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub enum DefeatCondition<'a> {
+    // The unit with a particular name is defeated
+    LordDies(&'a str),
+    // The map hasn't been won by the end of a turn
+    TurnLimit(Turns),
+}
+
+#[derive(
+    Clone, Copy, PartialEq, Eq, Hash, serde_repr::Serialize_repr, serde_repr::Deserialize_repr,
+)]
+#[repr(u8)]
+pub enum Faction {
+    Blue,
+    Red,
+    Green,
 }
 
 #[derive(
@@ -33,8 +80,111 @@ pub struct Unit<'a> {
     pub name: &'a str,
     pub class: Class,
     pub level: Level,
+    pub max_hp: HitPoints,
     pub hp: HitPoints,
+    pub attack: HitPoints,
+    pub defense: HitPoints,
+    pub accuracy: AccuracyPoints,
+    pub evade: AccuracyPoints,
+    pub critical: AccuracyPoints,
     pub movement: MapDistance,
     pub remaining_move: MapDistance,
     pub position: Vector<MapDistance>,
 }
+
+// Serialized format for the parts of a unit that can change during gameplay
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct SavedUnit {
+    pub level: Level,
+    pub max_hp: HitPoints,
+    pub hp: HitPoints,
+    pub remaining_move: MapDistance,
+    pub position: Vector<MapDistance>,
+    pub done: bool,
+}
+
+// Serialized format for saved games
+// Units are stored in the same order that they are placed on the map
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Save<'a> {
+    pub map: &'a str,
+    pub turn: Turns,
+    pub seed: u64,
+    pub units: Vec<SavedUnit>,
+    // Roster that the chapter was started with
+    #[serde(borrow)]
+    pub roster: Roster<'a>,
+}
+
+// A chapter of the campaign, which is played on a single map
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Chapter<'a> {
+    pub map: &'a str,
+    // Key used to look up the name of the chapter in the language pack
+    pub name: &'a str,
+}
+
+// Serialized format for the list of chapters in the campaign, in the order that they are played
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Campaign<'a> {
+    #[serde(borrow)]
+    pub chapters: Vec<Chapter<'a>>,
+}
+
+// Player units that are carried from one chapter to the next
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct Roster<'a> {
+    // Units that have survived, as they were at the end of the last chapter that they were in
+    #[serde(borrow)]
+    pub units: Vec<Unit<'a>>,
+    // Names of units that have fallen, which are left out of later chapters
+    #[serde(borrow)]
+    pub fallen: Vec<&'a str>,
+}
+
+// Serialized format for the player's progress through the campaign
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Progress<'a> {
+    // Index of the next chapter to play
+    pub chapter: u32,
+    #[serde(borrow)]
+    pub roster: Roster<'a>,
+}
+
+// Decides whether a count uses the "one" or "other" plural form when there is no "=N" form for it
+#[derive(Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub enum PluralRule {
+    // Only 1 uses "one", as in English and Spanish
+    #[default]
+    One,
+    // 0 and 1 use "one", as in French
+    ZeroOne,
+    // Every count uses "other", for languages that don't inflect for number
+    Other,
+}
+
+// A string in a language pack, which may have different forms depending on a count
+// Plural forms are keyed by "=N" for an exact count, "one", or "other"
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(untagged)]
+pub enum Message<'a> {
+    Text(&'a str),
+    #[serde(borrow)]
+    Plural(std::collections::HashMap<&'a str, &'a str>),
+}
+
+// Serialized format for language packs
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct LanguagePack<'a> {
+    #[serde(borrow)]
+    pub strings: std::collections::HashMap<&'a str, Message<'a>>,
+    #[serde(default)]
+    pub plural: PluralRule,
+}
+
+// Serialized list of the language packs that are available
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct LanguageIndex<'a> {
+    #[serde(borrow)]
+    pub languages: Vec<&'a str>,
+}
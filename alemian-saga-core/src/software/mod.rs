@@ -0,0 +1,315 @@
+mod font;
+
+use std::{cell, collections, io, path, rc};
+
+use async_trait::async_trait;
+
+use crate::headless::Headless;
+use crate::*;
+
+// Text is drawn with each pixel of the bitmap font scaled up to this many screen pixels
+const FONT_SCALE: u32 = 2;
+
+// Matches the offset between the requested position and the baseline used by the web version
+const TEXT_BASELINE_OFFSET: f64 = 10.0;
+
+const BACKGROUND_COLOR: [u8; 4] = [0, 0, 0, 255];
+const HIGHLIGHT_COLOR: [u8; 4] = [0x33, 0x33, 0xff, 128];
+const FONT_COLOR: [u8; 4] = [0, 0, 0, 255];
+
+// In-memory RGBA image with 8 bits per channel
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let pixels = BACKGROUND_COLOR
+            .iter()
+            .copied()
+            .cycle()
+            .take((width * height * 4) as usize)
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    // Decodes a PNG image
+    pub fn from_png<R: io::Read>(reader: R) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let (info, mut reader) = decoder.read_info()?;
+        let mut buffer = vec![0; info.buffer_size()];
+        reader.next_frame(&mut buffer)?;
+        let channels = info.color_type.samples();
+        let pixels = buffer
+            .chunks(channels)
+            .flat_map(|p| match p {
+                [gray] => [*gray, *gray, *gray, 255],
+                [gray, alpha] => [*gray, *gray, *gray, *alpha],
+                [r, g, b] => [*r, *g, *b, 255],
+                _ => [p[0], p[1], p[2], p[3]],
+            })
+            .collect();
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // Gets the pixel data, in rows from top to bottom with four bytes per pixel
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    // Encodes the image as a PNG
+    pub fn write_png<W: io::Write>(&self, writer: W) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)
+    }
+
+    // Saves the image to a PNG file
+    pub fn save_png(&self, path: &path::Path) -> Result<(), String> {
+        let file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+        self.write_png(io::BufWriter::new(file))
+            .map_err(|e| e.to_string())
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    // Blends a color over a pixel, ignoring pixels that are off of the image
+    fn blend_pixel(&mut self, x: i64, y: i64, color: [u8; 4]) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let i = ((y as u32 * self.width + x as u32) * 4) as usize;
+        let alpha = color[3] as u32;
+        for (old, new) in self.pixels[i..i + 3].iter_mut().zip(color.iter()) {
+            *old = ((*new as u32 * alpha + *old as u32 * (255 - alpha)) / 255) as u8;
+        }
+        let old_alpha = self.pixels[i + 3] as u32;
+        self.pixels[i + 3] = (alpha + old_alpha * (255 - alpha) / 255) as u8;
+    }
+
+    fn fill(&mut self, left: f64, top: f64, width: f64, height: f64, color: [u8; 4]) {
+        let (x0, y0) = (left.round() as i64, top.round() as i64);
+        let (x1, y1) = ((left + width).round() as i64, (top + height).round() as i64);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                self.blend_pixel(x, y, color);
+            }
+        }
+    }
+
+    // Draws another image scaled to fit a rectangle, using nearest neighbor sampling
+    fn draw_image(&mut self, img: &Framebuffer, left: f64, top: f64, width: f64, height: f64) {
+        let (x0, y0) = (left.round() as i64, top.round() as i64);
+        let (x1, y1) = ((left + width).round() as i64, (top + height).round() as i64);
+        if x1 <= x0 || y1 <= y0 || img.width == 0 || img.height == 0 {
+            return;
+        }
+        for y in y0..y1 {
+            let src_y = ((y - y0) * img.height as i64 / (y1 - y0)) as u32;
+            for x in x0..x1 {
+                let src_x = ((x - x0) * img.width as i64 / (x1 - x0)) as u32;
+                self.blend_pixel(x, y, img.get_pixel(src_x, src_y));
+            }
+        }
+    }
+
+    fn draw_text(&mut self, text: &str, x: f64, y: f64, max_width: f64) {
+        let advance = ((font::GLYPH_WIDTH + 1) * FONT_SCALE) as i64;
+        let left = x.round() as i64;
+        let right = (x + max_width).round() as i64;
+        let top =
+            (y + TEXT_BASELINE_OFFSET).round() as i64 - (font::GLYPH_HEIGHT * FONT_SCALE) as i64;
+        for (i, c) in text.chars().enumerate() {
+            let glyph_left = left + i as i64 * advance;
+            if glyph_left + advance > right + FONT_SCALE as i64 {
+                break;
+            }
+            for (row, bits) in font::get_glyph(c).iter().enumerate() {
+                for column in 0..font::GLYPH_WIDTH {
+                    if bits & (1 << (font::GLYPH_WIDTH - 1 - column)) != 0 {
+                        let px = glyph_left + (column * FONT_SCALE) as i64;
+                        let py = top + row as i64 * FONT_SCALE as i64;
+                        for dy in 0..FONT_SCALE as i64 {
+                            for dx in 0..FONT_SCALE as i64 {
+                                self.blend_pixel(px + dx, py + dy, FONT_COLOR);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Platform that renders the game into an in-memory framebuffer instead of a screen
+// Files, the clock and logging behave the same as on the headless platform
+pub struct Software {
+    files: Headless,
+    framebuffer: rc::Rc<cell::RefCell<Framebuffer>>,
+    images: cell::RefCell<collections::HashMap<String, Option<rc::Rc<Framebuffer>>>>,
+}
+
+impl Software {
+    // Files are looked up in each directory of search_path in order
+    pub fn new(
+        search_path: Vec<path::PathBuf>,
+        user_directory: path::PathBuf,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let screen_size = Vector {
+            x: width as f64,
+            y: height as f64,
+        };
+        Self {
+            files: Headless::new(search_path, user_directory, screen_size),
+            framebuffer: rc::Rc::new(cell::RefCell::new(Framebuffer::new(width, height))),
+            images: cell::RefCell::new(collections::HashMap::new()),
+        }
+    }
+
+    // Gets a handle to the framebuffer that the game is drawn to
+    // The handle stays valid after the platform has been moved into the game
+    pub fn framebuffer(&self) -> rc::Rc<cell::RefCell<Framebuffer>> {
+        self.framebuffer.clone()
+    }
+
+    // Decodes an image the first time it is drawn; images that fail to load are logged once
+    fn load_image(&self, path: &str) -> Option<rc::Rc<Framebuffer>> {
+        let mut images = self.images.borrow_mut();
+        let image = images.entry(path.to_owned()).or_insert_with(|| {
+            let decoded = self
+                .files
+                .read_file(path)
+                .and_then(|f| Framebuffer::from_png(f.as_slice()).map_err(|e| e.to_string()));
+            match decoded {
+                Ok(img) => Some(rc::Rc::new(img)),
+                Err(e) => {
+                    Self::log(format!("Error: Could not load image {}: {}", path, e).as_str());
+                    None
+                }
+            }
+        });
+        image.clone()
+    }
+}
+
+#[async_trait(?Send)]
+impl Platform for Software {
+    type Error = String;
+
+    type Image = String;
+
+    type InputType = String;
+
+    type MouseDistance = i32;
+
+    type ScreenDistance = f64;
+
+    type ImageFuture = futures::future::Ready<Option<String>>;
+
+    type File = Vec<u8>;
+
+    type UserFile = Vec<u8>;
+
+    type Instant = u64;
+
+    type Duration = u64;
+
+    fn draw_primitive(&self, img: &String, left: f64, top: f64, width: f64, height: f64) {
+        if let Some(image) = self.load_image(img) {
+            self.framebuffer
+                .borrow_mut()
+                .draw_image(&image, left, top, width, height);
+        }
+    }
+
+    fn draw_rectangle(&self, left: f64, top: f64, width: f64, height: f64) {
+        self.framebuffer
+            .borrow_mut()
+            .fill(left, top, width, height, HIGHLIGHT_COLOR);
+    }
+
+    fn draw_text_primitive(&self, text: &str, x: f64, y: f64, max_width: f64) {
+        self.framebuffer
+            .borrow_mut()
+            .draw_text(text, x, y, max_width);
+    }
+
+    fn string_to_input(input: &str) -> String {
+        input.to_owned()
+    }
+
+    fn get_width(&self) -> f64 {
+        self.files.get_width()
+    }
+
+    fn get_height(&self) -> f64 {
+        self.files.get_height()
+    }
+
+    // Images are identified by their path and decoded when they are first drawn
+    fn get_image(path: &str) -> Self::ImageFuture {
+        Headless::get_image(path)
+    }
+
+    async fn get_file(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.files.get_file(path).await
+    }
+
+    async fn get_user_file(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.files.get_user_file(path).await
+    }
+
+    async fn put_user_file(&self, path: &str, contents: &[u8]) -> Result<(), String> {
+        self.files.put_user_file(path, contents).await
+    }
+
+    fn log(msg: &str) {
+        Headless::log(msg);
+    }
+
+    fn take_captured_key(&self) -> Option<String> {
+        self.files.take_captured_key()
+    }
+
+    fn now() -> u64 {
+        Headless::now()
+    }
+
+    fn nanoseconds(ns: usize) -> u64 {
+        Headless::nanoseconds(ns)
+    }
+
+    fn duration_between(first: u64, second: u64) -> u64 {
+        Headless::duration_between(first, second)
+    }
+}
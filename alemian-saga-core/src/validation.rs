@@ -0,0 +1,315 @@
+use crate::*;
+use numeric_types::*;
+
+// A mistake in a map, along with where on the map it is
+pub struct Problem {
+    pub position: Option<Vector<MapDistance>>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some(p) => write!(
+                f,
+                "row {}, column {}: {}",
+                p.y.value, p.x.value, self.message
+            ),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+// Checks a map for mistakes that would otherwise only show up while the game is running
+// image_exists reports whether an image can be loaded from a path
+pub fn validate_map<F: Fn(&str) -> bool>(
+    map: &serialization::Map,
+    image_exists: F,
+) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    let tile_count = map.tile_types.len();
+    for ((row, column), index) in map.map.indexed_iter() {
+        if *index as usize >= tile_count {
+            problems.push(Problem {
+                position: Some(Vector {
+                    x: map_dist(column as i32),
+                    y: map_dist(row as i32),
+                }),
+                message: format!(
+                    "tile type {} does not exist (the map has {} tile types)",
+                    index, tile_count
+                ),
+            });
+        }
+    }
+
+    for tile_type in map.tile_types.iter() {
+        if !image_exists(tile_type.image) {
+            problems.push(Problem {
+                position: None,
+                message: format!(
+                    "image {} for tile type {} does not exist",
+                    tile_type.image, tile_type.name
+                ),
+            });
+        }
+    }
+
+    let (rows, columns) = map.map.dim();
+    let factions = [
+        (serialization::Faction::Blue, &map.blue),
+        (serialization::Faction::Red, &map.red),
+        (serialization::Faction::Green, &map.green),
+    ];
+    let mut occupied = std::collections::HashMap::new();
+    let mut unit_images = std::collections::HashSet::new();
+    // Objectives and the campaign roster find units by name
+    let mut names = std::collections::HashSet::new();
+    for (faction, units) in factions.iter() {
+        for u in units.iter() {
+            let p = u.position;
+            if !names.insert(u.name) {
+                problems.push(Problem {
+                    position: Some(p),
+                    message: format!("more than one unit is named {}", u.name),
+                });
+            }
+            let (x, y) = (p.x.value, p.y.value);
+            if x < 0 || y < 0 || x as usize >= columns || y as usize >= rows {
+                problems.push(Problem {
+                    position: Some(p),
+                    message: format!("unit {} is outside of the {}x{} map", u.name, columns, rows),
+                });
+            } else if let Some(other) = occupied.insert((x, y), u.name) {
+                problems.push(Problem {
+                    position: Some(p),
+                    message: format!("units {} and {} are on the same tile", other, u.name),
+                });
+            }
+            let image = detail::get_unit_image_path(*faction, u.class);
+            if unit_images.insert(image.clone()) && !image_exists(image.as_str()) {
+                problems.push(Problem {
+                    position: None,
+                    message: format!("image {} for unit {} does not exist", image, u.name),
+                });
+            }
+        }
+    }
+
+    // Objectives can only refer to units and tiles that are on the map
+    let check_unit = |name: &str, enemy: bool| {
+        let faction = factions
+            .iter()
+            .find(|(_, units)| units.iter().any(|u| u.name == name))
+            .map(|(faction, _)| *faction);
+        let message = match faction {
+            None => format!("objective unit {} is not on the map", name),
+            Some(f) if (f == serialization::Faction::Red) != enemy => {
+                let side = if enemy {
+                    "an enemy"
+                } else {
+                    "a player or allied unit"
+                };
+                format!("objective unit {} is not {}", name, side)
+            }
+            Some(_) => return None,
+        };
+        Some(Problem {
+            position: None,
+            message,
+        })
+    };
+    for objective in map.victory.iter() {
+        let problem = match objective {
+            serialization::Objective::DefeatBoss(name) => check_unit(name, true),
+            serialization::Objective::Protect { unit, .. } => check_unit(unit, false),
+            serialization::Objective::Seize(p) => {
+                let (x, y) = (p.x.value, p.y.value);
+                if x < 0 || y < 0 || x as usize >= columns || y as usize >= rows {
+                    Some(Problem {
+                        position: Some(*p),
+                        message: format!(
+                            "tile to seize is outside of the {}x{} map",
+                            columns, rows
+                        ),
+                    })
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        problems.extend(problem);
+    }
+    for condition in map.defeat.iter() {
+        if let serialization::DefeatCondition::LordDies(name) = condition {
+            problems.extend(check_unit(name, false));
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(name: &str, x: i32, y: i32) -> serialization::Unit<'_> {
+        serialization::Unit {
+            name,
+            class: serialization::Class::Noble,
+            level: level(1),
+            max_hp: hp(10),
+            hp: hp(10),
+            attack: hp(5),
+            defense: hp(1),
+            accuracy: accuracy_pts(80),
+            evade: accuracy_pts(10),
+            critical: accuracy_pts(5),
+            movement: map_dist(3),
+            remaining_move: map_dist(3),
+            position: Vector {
+                x: map_dist(x),
+                y: map_dist(y),
+            },
+        }
+    }
+
+    // A valid 2x2 map with a player lord and an enemy boss
+    fn map() -> serialization::Map<'static> {
+        serialization::Map {
+            tile_types: vec![serialization::TileType {
+                image: "grass.png",
+                name: "grass",
+                defense: hp(0),
+                evade: accuracy_pts(0),
+                move_cost: map_dist(1),
+                heal: hp(0),
+            }],
+            map: ndarray::Array2::zeros((2, 2)),
+            blue: vec![unit("Maylis", 0, 0)],
+            red: vec![unit("Gerard", 1, 1)],
+            green: Vec::new(),
+            seed: 0,
+            victory: Vec::new(),
+            defeat: Vec::new(),
+        }
+    }
+
+    fn messages(map: &serialization::Map) -> Vec<String> {
+        validate_map(map, |_| true)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn valid_maps_have_no_problems() {
+        assert!(messages(&map()).is_empty());
+    }
+
+    #[test]
+    fn units_outside_of_the_map_are_reported() {
+        let mut map = map();
+        map.blue.push(unit("Lost", 2, 0));
+        map.red.push(unit("Negative", 0, -1));
+        assert_eq!(
+            messages(&map),
+            [
+                "row 0, column 2: unit Lost is outside of the 2x2 map",
+                "row -1, column 0: unit Negative is outside of the 2x2 map",
+            ]
+        );
+    }
+
+    #[test]
+    fn overlapping_units_are_reported() {
+        let mut map = map();
+        map.green.push(unit("Ally", 1, 1));
+        assert_eq!(
+            messages(&map),
+            ["row 1, column 1: units Gerard and Ally are on the same tile"]
+        );
+    }
+
+    #[test]
+    fn duplicate_unit_names_are_reported() {
+        let mut map = map();
+        map.green.push(unit("Maylis", 1, 0));
+        assert_eq!(
+            messages(&map),
+            ["row 0, column 1: more than one unit is named Maylis"]
+        );
+    }
+
+    #[test]
+    fn unknown_tile_types_are_reported() {
+        let mut map = map();
+        map.map[[1, 0]] = 3;
+        assert_eq!(
+            messages(&map),
+            ["row 1, column 0: tile type 3 does not exist (the map has 1 tile types)"]
+        );
+    }
+
+    #[test]
+    fn missing_images_are_reported_once() {
+        let mut map = map();
+        map.blue.push(unit("Second", 1, 0));
+        let problems = validate_map(&map, |image| image != "blue/noble.png");
+        let problems: Vec<_> = problems.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            problems,
+            ["image blue/noble.png for unit Maylis does not exist"]
+        );
+    }
+
+    #[test]
+    fn objective_units_must_be_on_the_right_side() {
+        let mut map = map();
+        map.victory = vec![
+            serialization::Objective::DefeatBoss("Maylis"),
+            serialization::Objective::Protect {
+                unit: "Nobody",
+                turns: turns(3),
+            },
+        ];
+        map.defeat = vec![serialization::DefeatCondition::LordDies("Gerard")];
+        assert_eq!(
+            messages(&map),
+            [
+                "objective unit Maylis is not an enemy",
+                "objective unit Nobody is not on the map",
+                "objective unit Gerard is not a player or allied unit",
+            ]
+        );
+    }
+
+    #[test]
+    fn valid_objectives_have_no_problems() {
+        let mut map = map();
+        map.victory = vec![
+            serialization::Objective::DefeatBoss("Gerard"),
+            serialization::Objective::Seize(Vector {
+                x: map_dist(1),
+                y: map_dist(0),
+            }),
+        ];
+        map.defeat = vec![serialization::DefeatCondition::LordDies("Maylis")];
+        assert!(messages(&map).is_empty());
+    }
+
+    #[test]
+    fn seize_tiles_outside_of_the_map_are_reported() {
+        let mut map = map();
+        map.victory = vec![serialization::Objective::Seize(Vector {
+            x: map_dist(0),
+            y: map_dist(2),
+        })];
+        assert_eq!(
+            messages(&map),
+            ["row 2, column 0: tile to seize is outside of the 2x2 map"]
+        );
+    }
+}
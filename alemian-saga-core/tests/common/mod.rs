@@ -0,0 +1,101 @@
+// Builds the files that the game reads, so that tests don't depend on generated files
+// Each test binary only uses some of these helpers
+#![allow(dead_code)]
+
+use std::path;
+
+use alemian_saga_core::serialization::*;
+use alemian_saga_core::*;
+
+// Directories that a test game reads its files from and writes its user files to
+pub struct Fixture {
+    pub data: path::PathBuf,
+    pub user: path::PathBuf,
+}
+
+impl Fixture {
+    // Creates empty directories for a test, removing anything left over from an earlier run
+    pub fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join("alemian-saga-tests").join(name);
+        let _ = std::fs::remove_dir_all(&root);
+        let data = root.join("data");
+        let user = root.join("user");
+        std::fs::create_dir_all(data.join("language")).expect("Failed to create test directory");
+        std::fs::create_dir_all(&user).expect("Failed to create test directory");
+        Self { data, user }
+    }
+
+    // Writes a map file and an English language pack
+    pub fn write(&self, map: &Map) {
+        let map_file = file_format::encode_map(map).expect("Failed to encode map");
+        std::fs::write(self.data.join("map.map"), map_file).expect("Failed to write map");
+        let strings = [
+            ("Plain", "Plain"),
+            ("level", "lv {level}"),
+            ("hp", "{hp}/{max_hp}"),
+            ("victory", "Victory!"),
+            ("defeat", "Defeat"),
+        ];
+        let pack = LanguagePack {
+            strings: strings
+                .iter()
+                .map(|(k, v)| (*k, Message::Text(v)))
+                .collect(),
+            plural: PluralRule::One,
+        };
+        let pack_file =
+            file_format::encode_language_pack(&pack).expect("Failed to encode language");
+        std::fs::write(self.data.join("language").join("english.lang"), pack_file)
+            .expect("Failed to write language");
+    }
+}
+
+pub fn unit(name: &str, x: i32, y: i32) -> Unit<'_> {
+    Unit {
+        name,
+        class: Class::Noble,
+        level: level(1),
+        max_hp: hp(20),
+        hp: hp(20),
+        attack: hp(8),
+        defense: hp(2),
+        accuracy: accuracy_pts(90),
+        evade: accuracy_pts(10),
+        critical: accuracy_pts(5),
+        movement: map_dist(3),
+        remaining_move: map_dist(3),
+        position: Vector {
+            x: map_dist(x),
+            y: map_dist(y),
+        },
+    }
+}
+
+// Square map where every column uses its own tile type, so that columns can be told apart
+pub fn striped_map<'a>(size: usize, images: &'a [String]) -> Map<'a> {
+    Map {
+        tile_types: images
+            .iter()
+            .map(|image| TileType {
+                image: image.as_str(),
+                name: "Plain",
+                defense: hp(0),
+                evade: accuracy_pts(0),
+                move_cost: map_dist(1),
+                heal: hp(0),
+            })
+            .collect(),
+        map: ndarray::Array2::from_shape_fn((size, size), |(_, column)| column as u32),
+        blue: vec![unit("Maylis", 0, 0)],
+        red: vec![unit("Gerard", size as i32 - 1, size as i32 - 1)],
+        green: Vec::new(),
+        seed: 1,
+        victory: Vec::new(),
+        defeat: Vec::new(),
+    }
+}
+
+// Images for the columns of a striped map
+pub fn column_images(size: usize) -> Vec<String> {
+    (0..size).map(|c| format!("column-{}.png", c)).collect()
+}
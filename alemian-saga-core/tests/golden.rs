@@ -0,0 +1,97 @@
+#![cfg(feature = "software")]
+
+// Compares frames rendered by the software platform with reviewed screenshots in tests/golden
+// Run with UPDATE_GOLDEN=1 to replace the screenshots after an intended change to the graphics
+
+mod common;
+
+use std::path;
+
+use alemian_saga_core::headless::*;
+use alemian_saga_core::software::*;
+use alemian_saga_core::*;
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+
+fn golden_path(name: &str) -> path::PathBuf {
+    path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.png", name))
+}
+
+// Starts the game on a 4x4 map that uses the game's own images
+fn start(name: &str) -> (Session, std::rc::Rc<std::cell::RefCell<Framebuffer>>) {
+    let fixture = common::Fixture::new(name);
+    let images: Vec<_> = ["plain.png", "rough.png", "plain.png", "rough.png"]
+        .iter()
+        .map(|i| (*i).to_owned())
+        .collect();
+    fixture.write(&common::striped_map(4, &images));
+    let public = path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("public");
+    let platform = Software::new(
+        vec![fixture.data.clone(), public],
+        fixture.user.clone(),
+        WIDTH,
+        HEIGHT,
+    );
+    let framebuffer = platform.framebuffer();
+    (Session::new(platform, "english"), framebuffer)
+}
+
+fn assert_matches_golden(name: &str, frame: &Framebuffer) {
+    let path = golden_path(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        frame.save_png(&path).expect("Failed to save golden image");
+        return;
+    }
+    let file = std::fs::File::open(&path).unwrap_or_else(|e| {
+        panic!(
+            "{}: {}; run with UPDATE_GOLDEN=1 to create it",
+            path.display(),
+            e
+        )
+    });
+    let golden = Framebuffer::from_png(file).expect("Failed to decode golden image");
+    assert_eq!(
+        (golden.width(), golden.height()),
+        (frame.width(), frame.height())
+    );
+    let differences = golden
+        .pixels()
+        .chunks(4)
+        .zip(frame.pixels().chunks(4))
+        .filter(|(a, b)| a != b)
+        .count();
+    if differences > 0 {
+        let actual = std::env::temp_dir().join(format!("{}-actual.png", name));
+        let _ = frame.save_png(&actual);
+        panic!(
+            "{} pixels differ from {}; the frame was saved to {}",
+            differences,
+            path.display(),
+            actual.display()
+        );
+    }
+}
+
+#[test]
+fn map_on_start() {
+    let (session, framebuffer) = start("golden_start");
+    assert_matches_golden("start", &framebuffer.borrow());
+    session.finish();
+    assert!(Headless::take_log().iter().all(|l| !l.starts_with("Error")));
+}
+
+#[test]
+fn selected_unit_and_path() {
+    let (mut session, framebuffer) = start("golden_selected");
+    for e in [Event::Select, Event::Right, Event::Down].iter() {
+        session.send(*e).expect("The game stopped");
+    }
+    assert_matches_golden("selected", &framebuffer.borrow());
+    session.finish();
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "headless")]
+
+mod common;
+
+use alemian_saga_core::headless::*;
+use alemian_saga_core::*;
+
+const SCREEN_SIZE: f64 = 400.0;
+
+// Runs a script on a 4x4 striped map and returns everything that was drawn
+fn run(name: &str, script: Vec<Step>) -> Vec<DrawCall> {
+    let fixture = common::Fixture::new(name);
+    let images = common::column_images(4);
+    fixture.write(&common::striped_map(4, &images));
+    let platform = Headless::new(
+        vec![fixture.data.clone()],
+        fixture.user.clone(),
+        Vector {
+            x: SCREEN_SIZE,
+            y: SCREEN_SIZE,
+        },
+    );
+    let draw_calls = platform.draw_calls();
+    run_script(platform, script, "english").expect("Script did not finish");
+    assert!(Headless::take_log().iter().all(|l| !l.starts_with("Error")));
+    let calls = draw_calls.borrow().clone();
+    calls
+}
+
+fn was_drawn_at(calls: &[DrawCall], image: &str, x: f64, y: f64) -> bool {
+    calls.iter().any(|c| match c {
+        DrawCall::Image {
+            path, left, top, ..
+        } => path == image && (left - x).abs() < 0.5 && (top - y).abs() < 0.5,
+        _ => false,
+    })
+}
+
+// Zooming in to 3x3 tiles hides the last column until the screen pans right
+fn pan_right_script(wait: Option<u64>) -> Vec<Step> {
+    let mut script = vec![Step::from(Event::ZoomIn)];
+    script.extend(wait.map(Step::AdvanceClock));
+    script.push(Event::MouseMove(Vector { x: 390, y: 200 }).into());
+    script
+}
+
+#[test]
+fn edge_panning_waits_between_pans() {
+    let calls = run("edge_panning_waits", pan_right_script(None));
+    assert!(!was_drawn_at(
+        &calls,
+        "column-3.png",
+        SCREEN_SIZE * 2.0 / 3.0,
+        0.0
+    ));
+}
+
+#[test]
+fn edge_panning_pans_once_the_delay_has_passed() {
+    let calls = run("edge_panning_pans", pan_right_script(Some(200_000_000)));
+    assert!(was_drawn_at(
+        &calls,
+        "column-3.png",
+        SCREEN_SIZE * 2.0 / 3.0,
+        0.0
+    ));
+}
+
+#[test]
+fn selected_units_move_and_are_marked_done() {
+    let script = vec![
+        Event::Select.into(),
+        Event::Right.into(),
+        Event::Select.into(),
+    ];
+    let calls = run("units_move", script);
+    let last_unit = calls.iter().rev().find_map(|c| match c {
+        DrawCall::Image { path, left, .. } if path == "blue/noble.png" => Some(*left),
+        _ => None,
+    });
+    assert_eq!(last_unit, Some(SCREEN_SIZE / 4.0));
+    assert!(was_drawn_at(&calls, "done.png", SCREEN_SIZE / 4.0, 0.0));
+}
+
+#[test]
+fn edge_panning_can_be_turned_off() {
+    let mut script = vec![Step::from(Event::ToggleEdgePanning)];
+    script.extend(pan_right_script(Some(200_000_000)));
+    let calls = run("edge_panning_off", script);
+    assert!(!was_drawn_at(
+        &calls,
+        "column-3.png",
+        SCREEN_SIZE * 2.0 / 3.0,
+        0.0
+    ));
+}
+
+fn highlights(calls: &[DrawCall]) -> usize {
+    calls
+        .iter()
+        .filter(|c| matches!(c, DrawCall::Rectangle { .. }))
+        .count()
+}
+
+// Drags the zoomed in map away from Maylis and back again, then clicks on her
+fn drag_then_click_script(between: Option<Event<i32>>) -> Vec<Step> {
+    let mut script = vec![
+        Step::from(Event::ZoomIn),
+        Event::MouseDown(Vector { x: 390, y: 200 }).into(),
+        Event::MouseMove(Vector { x: 200, y: 200 }).into(),
+        Event::MouseMove(Vector { x: 390, y: 200 }).into(),
+        Event::MouseUp(Vector { x: 390, y: 200 }).into(),
+    ];
+    script.extend(between.map(Step::from));
+    script.push(Event::MouseClick(Vector { x: 50, y: 50 }).into());
+    script
+}
+
+#[test]
+fn only_the_click_that_ends_a_drag_is_ignored() {
+    let ignored = run("click_after_drag", drag_then_click_script(None));
+    let selected = run(
+        "click_after_other_event",
+        drag_then_click_script(Some(Event::Redraw)),
+    );
+    assert_eq!(highlights(&ignored), 0);
+    assert!(highlights(&selected) > 0);
+}
@@ -2,7 +2,10 @@
 #![feature(unboxed_closures)]
 #![feature(fn_traits)]
 
+use std::cell;
+use std::collections;
 use std::pin;
+use std::rc;
 use std::task;
 
 use async_trait::async_trait;
@@ -17,9 +20,16 @@ const HOST: &str = "https://alemiansaga.web.app/";
 const FONT: &str = "1.5rem serif";
 const HIGHLIGHT_COLOR: &str = "#3333ff";
 const FONT_COLOR: &str = "black";
+// Language used until the player picks a different one in game
 const LANGUAGE: &str = "english";
 const LOCALE: &str = "us";
-const EVENT_QUEUE_CAPACITY: usize = 8;
+const EVENT_QUEUE_CAPACITY: usize = 32;
+
+// Distance in pixels that a finger can move before a touch stops counting as a tap
+const TAP_DISTANCE: f64 = 10.0;
+
+// Factor that the distance between two fingers must change by to zoom one step
+const PINCH_STEP: f64 = 1.25;
 
 // functionality imported from javascript libraries
 #[wasm_bindgen]
@@ -45,6 +55,9 @@ extern "C" {
     #[wasm_bindgen(method)]
     fn toUint8Array(this: &Blob) -> js_sys::Uint8Array;
 
+    #[wasm_bindgen(js_namespace = ["firebase", "firestore", "Blob"], js_name = fromUint8Array)]
+    fn from_uint8_array(array: &js_sys::Uint8Array) -> Blob;
+
     #[wasm_bindgen(method)]
     fn doc(this: &CollectionReference, documentPath: &js_sys::JsString) -> DocumentReference;
 
@@ -57,6 +70,9 @@ extern "C" {
     #[wasm_bindgen(method)]
     fn get(this: &DocumentReference) -> js_sys::Promise;
 
+    #[wasm_bindgen(method)]
+    fn set(this: &DocumentReference, data: &js_sys::Object) -> js_sys::Promise;
+
     #[wasm_bindgen(method, getter)]
     fn exists(this: &DocumentSnapshot) -> bool;
 
@@ -141,6 +157,44 @@ fn send(
     }
 }
 
+// State of the fingers currently touching the screen
+#[derive(Default)]
+struct TouchState {
+    start: Option<alemian_saga_core::Vector<i32>>,
+    moved: bool,
+    pinch_distance: f64,
+    // Latest position of a drag that didn't fit in the event queue
+    pending_move: Option<alemian_saga_core::Vector<i32>>,
+}
+
+// Gets the position of a touch relative to the game canvas
+fn touch_pos(
+    canvas: &web_sys::HtmlCanvasElement,
+    touch: &web_sys::Touch,
+) -> alemian_saga_core::Vector<i32> {
+    let rect = canvas.get_bounding_client_rect();
+    alemian_saga_core::Vector {
+        x: touch.client_x() - rect.left() as i32,
+        y: touch.client_y() - rect.top() as i32,
+    }
+}
+
+// Gets the distance between two touches and the point halfway between them
+fn pinch(
+    canvas: &web_sys::HtmlCanvasElement,
+    touches: &web_sys::TouchList,
+) -> Option<(f64, alemian_saga_core::Vector<i32>)> {
+    let first = touch_pos(canvas, &touches.get(0)?);
+    let second = touch_pos(canvas, &touches.get(1)?);
+    let dx = f64::from(first.x - second.x);
+    let dy = f64::from(first.y - second.y);
+    let center = alemian_saga_core::Vector {
+        x: (first.x + second.x) / 2,
+        y: (first.y + second.y) / 2,
+    };
+    Some((dx.hypot(dy), center))
+}
+
 struct WebError {
     msg: String,
 }
@@ -168,9 +222,20 @@ struct WebBrowser<'a> {
     web_client: reqwest::Client,
     host: &'a str,
     user_files: CollectionReference,
+    key_bindings:
+        rc::Rc<cell::RefCell<collections::HashMap<String, alemian_saga_core::Event<i32>>>>,
+    capturing_key: rc::Rc<cell::Cell<bool>>,
+    captured_key: rc::Rc<cell::RefCell<Option<String>>>,
     _keyboard_handler: Option<gloo_events::EventListener>,
     _resize_handler: gloo_events::EventListener,
     _mouse_handler: gloo_events::EventListener,
+    _mouse_down_handler: gloo_events::EventListener,
+    _mouse_up_handler: gloo_events::EventListener,
+    _click_handler: gloo_events::EventListener,
+    _context_menu_handler: gloo_events::EventListener,
+    _touch_start_handler: gloo_events::EventListener,
+    _touch_move_handler: gloo_events::EventListener,
+    _touch_end_handler: gloo_events::EventListener,
     _scroll_handler: gloo_events::EventListener,
 }
 
@@ -249,16 +314,211 @@ impl<'a> WebBrowser<'a> {
                 }
             });
 
+        let mut mouse_down_event_queue = event_queue.clone();
+
+        let mouse_down_handler =
+            gloo_events::EventListener::new(&document_element, "mousedown", move |e| {
+                if let Some(mouse_event) = e.dyn_ref::<web_sys::MouseEvent>() {
+                    if mouse_event.button() == 0 {
+                        send(
+                            &mut mouse_down_event_queue,
+                            alemian_saga_core::Event::MouseDown(alemian_saga_core::Vector {
+                                x: mouse_event.offset_x(),
+                                y: mouse_event.offset_y(),
+                            }),
+                        );
+                    }
+                }
+            });
+
+        let mut mouse_up_event_queue = event_queue.clone();
+
+        let mouse_up_handler =
+            gloo_events::EventListener::new(&document_element, "mouseup", move |e| {
+                if let Some(mouse_event) = e.dyn_ref::<web_sys::MouseEvent>() {
+                    if mouse_event.button() == 0 {
+                        send(
+                            &mut mouse_up_event_queue,
+                            alemian_saga_core::Event::MouseUp(alemian_saga_core::Vector {
+                                x: mouse_event.offset_x(),
+                                y: mouse_event.offset_y(),
+                            }),
+                        );
+                    }
+                }
+            });
+
+        let mut click_event_queue = event_queue.clone();
+
+        let click_handler = gloo_events::EventListener::new(&document_element, "click", move |e| {
+            if let Some(mouse_event) = e.dyn_ref::<web_sys::MouseEvent>() {
+                send(
+                    &mut click_event_queue,
+                    alemian_saga_core::Event::MouseClick(alemian_saga_core::Vector {
+                        x: mouse_event.offset_x(),
+                        y: mouse_event.offset_y(),
+                    }),
+                );
+            }
+        });
+
+        let mut context_menu_event_queue = event_queue.clone();
+
+        // Right clicks cancel instead of opening the browser's context menu
+        let context_menu_handler = gloo_events::EventListener::new_with_options(
+            &document_element,
+            "contextmenu",
+            gloo_events::EventListenerOptions::enable_prevent_default(),
+            move |e| {
+                e.prevent_default();
+                send(
+                    &mut context_menu_event_queue,
+                    alemian_saga_core::Event::RightClick,
+                );
+            },
+        );
+
+        let touch_state = rc::Rc::new(cell::RefCell::new(TouchState::default()));
+
+        // Touch handlers prevent default so that the browser doesn't scroll or zoom the page
+        let mut touch_start_event_queue = event_queue.clone();
+        let touch_start_state = touch_state.clone();
+        let touch_start_canvas = canvas.clone();
+        let touch_start_handler = gloo_events::EventListener::new_with_options(
+            &document_element,
+            "touchstart",
+            gloo_events::EventListenerOptions::enable_prevent_default(),
+            move |e| {
+                if let Some(touch_event) = e.dyn_ref::<web_sys::TouchEvent>() {
+                    e.prevent_default();
+                    let touches = touch_event.touches();
+                    let mut state = touch_start_state.borrow_mut();
+                    if touches.length() == 1 {
+                        if let Some(touch) = touches.get(0) {
+                            let pos = touch_pos(&touch_start_canvas, &touch);
+                            *state = TouchState {
+                                start: Some(pos),
+                                moved: false,
+                                pinch_distance: 0.0,
+                                pending_move: None,
+                            };
+                            send(
+                                &mut touch_start_event_queue,
+                                alemian_saga_core::Event::TouchStart(pos),
+                            );
+                        }
+                    } else if let Some((distance, _)) = pinch(&touch_start_canvas, &touches) {
+                        // A second finger turns the touch into a pinch, which is never a tap
+                        state.moved = true;
+                        state.pinch_distance = distance;
+                        send(
+                            &mut touch_start_event_queue,
+                            alemian_saga_core::Event::TouchEnd,
+                        );
+                    }
+                }
+            },
+        );
+
+        let mut touch_move_event_queue = event_queue.clone();
+        let touch_move_state = touch_state.clone();
+        let touch_move_canvas = canvas.clone();
+        let touch_move_handler = gloo_events::EventListener::new_with_options(
+            &document_element,
+            "touchmove",
+            gloo_events::EventListenerOptions::enable_prevent_default(),
+            move |e| {
+                if let Some(touch_event) = e.dyn_ref::<web_sys::TouchEvent>() {
+                    e.prevent_default();
+                    let touches = touch_event.touches();
+                    let mut state = touch_move_state.borrow_mut();
+                    if touches.length() == 1 {
+                        if let (Some(touch), Some(start)) = (touches.get(0), state.start) {
+                            let pos = touch_pos(&touch_move_canvas, &touch);
+                            let dx = f64::from(pos.x - start.x);
+                            let dy = f64::from(pos.y - start.y);
+                            if dx.hypot(dy) > TAP_DISTANCE {
+                                state.moved = true;
+                            }
+                            // Only the latest position of a drag matters, so while the queue is
+                            // full a single position is held back instead of queueing every move
+                            if state.moved {
+                                let event = alemian_saga_core::Event::TouchMove(pos);
+                                let sent = touch_move_event_queue.try_send(event).is_ok();
+                                state.pending_move = if sent { None } else { Some(pos) };
+                            }
+                        }
+                    } else if let Some((distance, center)) = pinch(&touch_move_canvas, &touches) {
+                        if distance > state.pinch_distance * PINCH_STEP {
+                            state.pinch_distance = distance;
+                            send(
+                                &mut touch_move_event_queue,
+                                alemian_saga_core::Event::ZoomInAt(center),
+                            );
+                        } else if distance * PINCH_STEP < state.pinch_distance {
+                            state.pinch_distance = distance;
+                            send(
+                                &mut touch_move_event_queue,
+                                alemian_saga_core::Event::ZoomOutAt(center),
+                            );
+                        }
+                    }
+                }
+            },
+        );
+
+        let mut touch_end_event_queue = event_queue.clone();
+        let touch_end_state = touch_state;
+        let touch_end_handler = gloo_events::EventListener::new_with_options(
+            &document_element,
+            "touchend",
+            gloo_events::EventListenerOptions::enable_prevent_default(),
+            move |e| {
+                if let Some(touch_event) = e.dyn_ref::<web_sys::TouchEvent>() {
+                    e.prevent_default();
+                    if touch_event.touches().length() == 0 {
+                        let state = touch_end_state.replace(TouchState::default());
+                        if let Some(pos) = state.pending_move {
+                            send(
+                                &mut touch_end_event_queue,
+                                alemian_saga_core::Event::TouchMove(pos),
+                            );
+                        }
+                        send(
+                            &mut touch_end_event_queue,
+                            alemian_saga_core::Event::TouchEnd,
+                        );
+                        if let (false, Some(start)) = (state.moved, state.start) {
+                            send(
+                                &mut touch_end_event_queue,
+                                alemian_saga_core::Event::Tap(start),
+                            );
+                        }
+                    }
+                }
+            },
+        );
+
         let mut scroll_event_queue = event_queue.clone();
 
         let scroll_handler =
             gloo_events::EventListener::new(&document_element, "wheel", move |e| {
                 if let Some(wheel_event) = e.dyn_ref::<web_sys::WheelEvent>() {
                     let delta_y = wheel_event.delta_y();
+                    let pointer = alemian_saga_core::Vector {
+                        x: wheel_event.offset_x(),
+                        y: wheel_event.offset_y(),
+                    };
                     if delta_y < 0.0 {
-                        send(&mut scroll_event_queue, alemian_saga_core::Event::ZoomIn);
+                        send(
+                            &mut scroll_event_queue,
+                            alemian_saga_core::Event::ZoomInAt(pointer),
+                        );
                     } else if delta_y > 0.0 {
-                        send(&mut scroll_event_queue, alemian_saga_core::Event::ZoomOut);
+                        send(
+                            &mut scroll_event_queue,
+                            alemian_saga_core::Event::ZoomOutAt(pointer),
+                        );
                     }
                 }
             });
@@ -276,9 +536,19 @@ impl<'a> WebBrowser<'a> {
             web_client,
             host,
             user_files,
+            key_bindings: rc::Rc::new(cell::RefCell::new(collections::HashMap::new())),
+            capturing_key: rc::Rc::new(cell::Cell::new(false)),
+            captured_key: rc::Rc::new(cell::RefCell::new(None)),
             _keyboard_handler: None,
             _resize_handler: resize_handler,
             _mouse_handler: mouse_handler,
+            _mouse_down_handler: mouse_down_handler,
+            _mouse_up_handler: mouse_up_handler,
+            _click_handler: click_handler,
+            _context_menu_handler: context_menu_handler,
+            _touch_start_handler: touch_start_handler,
+            _touch_move_handler: touch_move_handler,
+            _touch_end_handler: touch_end_handler,
             _scroll_handler: scroll_handler,
         };
 
@@ -286,13 +556,22 @@ impl<'a> WebBrowser<'a> {
             .get_keybindings(LOCALE)
             .await
             .ok_or_else(|| JsValue::from_str("Failed to get keybindings"))?;
+        ret.key_bindings.replace(key_bindings);
+        let key_bindings = ret.key_bindings.clone();
+        let capturing_key = ret.capturing_key.clone();
+        let captured_key = ret.captured_key.clone();
 
         ret._keyboard_handler = Some(gloo_events::EventListener::new(
             &document_element,
             "keydown",
             move |e| {
                 if let Some(keyboard_event) = e.dyn_ref::<web_sys::KeyboardEvent>() {
-                    if let Some(&game_event) = key_bindings.get(&keyboard_event.key()) {
+                    if capturing_key.replace(false) {
+                        captured_key.replace(Some(keyboard_event.key()));
+                        send(&mut event_queue, alemian_saga_core::Event::KeyCaptured);
+                    } else if let Some(&game_event) =
+                        key_bindings.borrow().get(&keyboard_event.key())
+                    {
                         send(&mut event_queue, game_event);
                     }
                 }
@@ -405,6 +684,10 @@ impl alemian_saga_core::Platform for WebBrowser<'_> {
         }
     }
 
+    fn locale(&self) -> &str {
+        LOCALE
+    }
+
     async fn get_user_file(&self, path: &str) -> Result<Self::UserFile, Self::Error> {
         let promise = self.user_files.doc(&path.into()).get();
         let future: wasm_bindgen_futures::JsFuture = promise.into();
@@ -423,10 +706,35 @@ impl alemian_saga_core::Platform for WebBrowser<'_> {
         }
     }
 
+    async fn put_user_file(&self, path: &str, contents: &[u8]) -> Result<(), Self::Error> {
+        let data = js_sys::Object::new();
+        let blob = Blob::from_uint8_array(&js_sys::Uint8Array::from(contents));
+        js_sys::Reflect::set(&data, &"contents".into(), &blob)?;
+        let promise = self.user_files.doc(&path.into()).set(&data);
+        let future: wasm_bindgen_futures::JsFuture = promise.into();
+        future.await?;
+        Ok(())
+    }
+
     fn string_to_input(input: &str) -> Self::InputType {
         input.to_owned()
     }
 
+    fn capture_key(&self) {
+        self.capturing_key.set(true);
+    }
+
+    fn take_captured_key(&self) -> Option<String> {
+        self.captured_key.take()
+    }
+
+    fn set_keybindings(
+        &self,
+        bindings: collections::HashMap<String, alemian_saga_core::Event<i32>>,
+    ) {
+        self.key_bindings.replace(bindings);
+    }
+
     fn log(msg: &str) {
         web_sys::console::log_1(&wasm_bindgen::JsValue::from_str(msg));
     }
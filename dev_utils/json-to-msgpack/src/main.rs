@@ -1,8 +1,18 @@
+mod options;
+mod tiled;
+
+use alemian_saga_core::file_format;
 use alemian_saga_core::numeric_types::*;
 use alemian_saga_core::serialization;
-use std::collections;
+use alemian_saga_core::validation;
+use std::{collections, path};
 
-const LANGUAGES: [&'static str; 1] = ["english"];
+// Languages to build, along with the language used for strings that haven't been translated and
+// the rule for choosing between plural forms
+const LANGUAGES: [(&str, Option<&str>, serialization::PluralRule); 2] = [
+    ("english", None, serialization::PluralRule::One),
+    ("spanish", Some("english"), serialization::PluralRule::One),
+];
 
 #[derive(Clone, Copy, serde::Deserialize)]
 enum JsonClass {
@@ -20,90 +30,665 @@ struct JsonUnit {
     class: JsonClass,
     name: String,
     level: Level,
-    hp: HitPoints,
+    max_hp: HitPoints,
+    hp: Option<HitPoints>,
+    attack: HitPoints,
+    defense: HitPoints,
+    accuracy: AccuracyPoints,
+    evade: AccuracyPoints,
+    critical: AccuracyPoints,
     position: alemian_saga_core::Vector<serialization::MapDistance>,
     movement: MapDistance,
     remaining_move: MapDistance,
 }
 
+// Grid of tile type names, stored one row after another
+#[derive(serde::Deserialize)]
+struct JsonGrid {
+    dim: [usize; 2],
+    data: Vec<String>,
+}
+
+// Converts a grid into a two dimensional array, describing how it is malformed if it isn't one
+fn json_grid_to_array(grid: JsonGrid) -> Result<ndarray::Array2<String>, String> {
+    let [rows, columns] = grid.dim;
+    let len = grid.data.len();
+    if len != rows * columns {
+        let detail = if columns > 0 && !len.is_multiple_of(columns) {
+            format!("the last row has {} of {} tiles", len % columns, columns)
+        } else {
+            format!("that is {} rows", len / columns.max(1))
+        };
+        return Err(format!(
+            "map.data has {} tiles, but map.dim [{}, {}] needs {}; {}",
+            len,
+            rows,
+            columns,
+            rows * columns,
+            detail
+        ));
+    }
+    ndarray::Array2::from_shape_vec((rows, columns), grid.data).map_err(|e| e.to_string())
+}
+
 #[allow(non_snake_case)]
 #[derive(serde::Deserialize)]
 #[serde(tag = "schema")]
 enum JsonContent {
     Map {
         tileTypes: collections::HashMap<String, TileTypeInfo>,
-        map: ndarray::Array2<String>,
+        map: JsonGrid,
         blue: Vec<JsonUnit>,
+        #[serde(default)]
+        red: Vec<JsonUnit>,
+        #[serde(default)]
+        green: Vec<JsonUnit>,
+        #[serde(default)]
+        seed: u64,
+        #[serde(default)]
+        victory: Vec<JsonObjective>,
+        #[serde(default)]
+        defeat: Vec<JsonDefeatCondition>,
+    },
+    Campaign {
+        chapters: Vec<JsonChapter>,
     },
 }
 
+#[derive(serde::Deserialize)]
+#[serde(tag = "type")]
+enum JsonObjective {
+    Rout,
+    Seize {
+        position: alemian_saga_core::Vector<MapDistance>,
+    },
+    Survive {
+        turns: Turns,
+    },
+    DefeatBoss {
+        unit: String,
+    },
+    Protect {
+        unit: String,
+        turns: Turns,
+    },
+}
+
+fn json_objective_to_objective(json_objective: &JsonObjective) -> serialization::Objective<'_> {
+    match json_objective {
+        JsonObjective::Rout => serialization::Objective::Rout,
+        JsonObjective::Seize { position } => serialization::Objective::Seize(*position),
+        JsonObjective::Survive { turns } => serialization::Objective::Survive(*turns),
+        JsonObjective::DefeatBoss { unit } => serialization::Objective::DefeatBoss(unit.as_str()),
+        JsonObjective::Protect { unit, turns } => serialization::Objective::Protect {
+            unit: unit.as_str(),
+            turns: *turns,
+        },
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(tag = "type")]
+enum JsonDefeatCondition {
+    LordDies { unit: String },
+    TurnLimit { turns: Turns },
+}
+
+fn json_defeat_to_defeat(json_defeat: &JsonDefeatCondition) -> serialization::DefeatCondition<'_> {
+    match json_defeat {
+        JsonDefeatCondition::LordDies { unit } => {
+            serialization::DefeatCondition::LordDies(unit.as_str())
+        }
+        JsonDefeatCondition::TurnLimit { turns } => {
+            serialization::DefeatCondition::TurnLimit(*turns)
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct JsonChapter {
+    map: String,
+    name: String,
+}
+
+fn json_units_to_units(json_units: &[JsonUnit]) -> Vec<serialization::Unit<'_>> {
+    json_units
+        .iter()
+        .map(|j| serialization::Unit {
+            class: json_class_to_class(j.class),
+            name: j.name.as_str(),
+            max_hp: j.max_hp,
+            hp: j.hp.unwrap_or(j.max_hp),
+            attack: j.attack,
+            defense: j.defense,
+            accuracy: j.accuracy,
+            evade: j.evade,
+            critical: j.critical,
+            level: j.level,
+            position: j.position,
+            movement: j.movement,
+            remaining_move: j.remaining_move,
+        })
+        .collect()
+}
+
+// A string in a language file, which is either plain text or a set of plural forms
+#[derive(Clone, serde::Deserialize)]
+#[serde(untagged)]
+enum JsonMessage {
+    Text(String),
+    Plural(collections::HashMap<String, String>),
+}
+
+fn json_message_to_message(json_message: &JsonMessage) -> serialization::Message<'_> {
+    match json_message {
+        JsonMessage::Text(text) => serialization::Message::Text(text.as_str()),
+        JsonMessage::Plural(forms) => serialization::Message::Plural(
+            forms
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect(),
+        ),
+    }
+}
+
 #[derive(serde::Deserialize)]
 struct TileTypeInfo {
     image: String,
     move_cost: MapDistance,
     defense: HitPoints,
     evade: AccuracyPoints,
+    heal: Option<HitPoints>,
 }
 
-#[allow(non_snake_case)]
-fn main() {
-    let out_folder = std::path::Path::new("../generated-files");
-    let _ = std::fs::create_dir(out_folder);
-    for f in std::fs::read_dir("../../json-files").unwrap() {
-        let file = f.unwrap();
-        if file.file_type().unwrap().is_file() {
-            let mut path = file.path();
-            let reader = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
-            let json: JsonContent = serde_json::from_reader(reader).unwrap();
-            match json {
-                JsonContent::Map {
-                    tileTypes,
-                    map,
-                    blue,
-                } => {
-                    let mut name_to_index = collections::HashMap::new();
-                    let out_blue = blue
-                        .iter()
-                        .map(|j| serialization::Unit {
-                            class: json_class_to_class(j.class),
-                            name: j.name.as_str(),
-                            hp: j.hp,
-                            level: j.level,
-                            position: j.position,
-                            movement: j.movement,
-                            remaining_move: j.remaining_move,
-                        })
-                        .collect::<Vec<_>>();
-                    for l in LANGUAGES.iter() {
-                        let lang_file =
-                            std::fs::File::open(&format!("../../language/{}.json", l)).unwrap();
-                        let lang_reader = std::io::BufReader::new(lang_file);
-                        let string_map: collections::HashMap<String, String> =
-                            serde_json::from_reader(lang_reader).unwrap();
-                        let mut tile_types = vec![];
-                        for (i, (k, v)) in tileTypes.iter().enumerate() {
-                            name_to_index.insert(k.clone(), i as u32);
-                            tile_types.push(serialization::TileType {
-                                name: string_map.get(k).unwrap().as_str(),
-                                image: v.image.as_str(),
-                                defense: v.defense,
-                                evade: v.evade,
-                                move_cost: v.move_cost,
-                            });
-                        }
-                        let new_map = serialization::Map {
-                            tile_types,
-                            map: map.map(|x| *name_to_index.get(x).unwrap()),
-                            blue: out_blue.clone(),
-                        };
-                        path.set_extension("map");
-                        let out_path = out_folder.join(l).join(path.file_name().unwrap());
-                        let _ = std::fs::create_dir(out_folder.join(l));
-                        let mut out_file = std::fs::File::create(out_path).unwrap();
-                        rmp_serde::encode::write(&mut out_file, &new_map).unwrap();
+// Strings in a language, including those taken from its fallback languages
+type StringTable = collections::HashMap<String, JsonMessage>;
+
+// Gets a language followed by the languages that it falls back to, in order
+fn fallback_chain(language: &str) -> Vec<&str> {
+    let mut chain = Vec::new();
+    let mut next = Some(language);
+    while let Some(l) = next {
+        if chain.contains(&l) {
+            break;
+        }
+        chain.push(l);
+        next = LANGUAGES
+            .iter()
+            .find(|(name, _, _)| *name == l)
+            .and_then(|(_, fallback, _)| *fallback);
+    }
+    chain
+}
+
+// Formats an error message that refers to a file
+fn file_error<E: std::fmt::Display>(path: &path::Path, err: E) -> String {
+    format!("{}: {}", path.display(), err)
+}
+
+// Formats a JSON error message with the line and column that it occurred on
+fn json_error(path: &path::Path, err: serde_json::Error) -> String {
+    let msg = err.to_string();
+    let msg = msg
+        .rsplit_once(" at line ")
+        .map_or(msg.as_str(), |(m, _)| m);
+    format!(
+        "{}:{}:{}: {}",
+        path.display(),
+        err.line(),
+        err.column(),
+        msg
+    )
+}
+
+// Reads and parses a JSON file
+fn read_json<T: serde::de::DeserializeOwned>(path: &path::Path) -> Result<T, String> {
+    let file = std::fs::File::open(path).map_err(|e| file_error(path, e))?;
+    serde_json::from_reader(std::io::BufReader::new(file)).map_err(|e| json_error(path, e))
+}
+
+// Writes a file that was encoded in one of the game's file formats
+fn write_file(
+    path: &path::Path,
+    encoded: Result<Vec<u8>, file_format::Error>,
+) -> Result<(), String> {
+    let bytes = encoded.map_err(|e| file_error(path, e))?;
+    std::fs::write(path, bytes).map_err(|e| file_error(path, e))
+}
+
+// Compiles the language files into language packs and returns the strings in each language
+// Strings missing from a language are taken from its fallback languages and reported
+fn compile_languages(
+    options: &options::Options,
+) -> Result<Vec<(String, StringTable)>, Vec<String>> {
+    let mut files = collections::HashMap::new();
+    let mut errors = Vec::new();
+    for l in options.languages.iter() {
+        for c in fallback_chain(l) {
+            if files.contains_key(c) {
+                continue;
+            }
+            let path = options.language_dir.join(format!("{}.json", c));
+            match read_json::<StringTable>(&path) {
+                Ok(string_map) => {
+                    files.insert(c, string_map);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let all_keys: collections::BTreeSet<&String> = files.values().flat_map(|f| f.keys()).collect();
+    let lang_folder = options.output.join("language");
+    if !options.check {
+        std::fs::create_dir_all(&lang_folder).map_err(|e| vec![file_error(&lang_folder, e)])?;
+    }
+    let mut ret = Vec::new();
+    for l in options.languages.iter() {
+        let chain = fallback_chain(l);
+        let mut strings = collections::HashMap::new();
+        let mut fallback_keys = Vec::new();
+        let mut missing_keys = Vec::new();
+        for k in all_keys.iter() {
+            match chain.iter().find_map(|c| files.get(c)?.get(*k)) {
+                Some(message) => {
+                    if !files[l.as_str()].contains_key(*k) {
+                        fallback_keys.push(k.as_str());
                     }
+                    strings.insert((*k).clone(), message.clone());
                 }
+                None => missing_keys.push(k.as_str()),
+            }
+        }
+        if !fallback_keys.is_empty() {
+            println!(
+                "{} is missing {} strings, using {}: {}",
+                l,
+                fallback_keys.len(),
+                chain[1..].join(", "),
+                fallback_keys.join(", ")
+            );
+        }
+        if !missing_keys.is_empty() {
+            println!(
+                "{} has no translation for {} strings: {}",
+                l,
+                missing_keys.len(),
+                missing_keys.join(", ")
+            );
+        }
+        if !options.check {
+            let pack = serialization::LanguagePack {
+                strings: strings
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), json_message_to_message(v)))
+                    .collect(),
+                plural: LANGUAGES
+                    .iter()
+                    .find(|(name, _, _)| name == l)
+                    .map_or_else(Default::default, |(_, _, rule)| *rule),
+            };
+            let out_path = lang_folder.join(format!("{}.lang", l));
+            if let Err(e) = write_file(&out_path, file_format::encode_language_pack(&pack)) {
+                errors.push(e);
+            }
+        }
+        ret.push((l.clone(), strings));
+    }
+    if !options.check {
+        let index = serialization::LanguageIndex {
+            languages: options.languages.iter().map(String::as_str).collect(),
+        };
+        let out_path = lang_folder.join("index.lang");
+        if let Err(e) = write_file(&out_path, file_format::encode_language_index(&index)) {
+            errors.push(e);
+        }
+    }
+    if errors.is_empty() {
+        Ok(ret)
+    } else {
+        Err(errors)
+    }
+}
+
+// A map read from one of the supported input formats, before it has been checked and written
+struct MapSource {
+    tile_types: Vec<(String, TileTypeInfo)>,
+    map: ndarray::Array2<u32>,
+    blue: Vec<JsonUnit>,
+    red: Vec<JsonUnit>,
+    green: Vec<JsonUnit>,
+    seed: u64,
+    victory: Vec<JsonObjective>,
+    defeat: Vec<JsonDefeatCondition>,
+}
+
+// Contents of an input file
+enum Input {
+    Map(MapSource),
+    Campaign(Vec<JsonChapter>),
+    // Tiled tilesets are read along with the maps that use them, so they aren't converted alone
+    Tileset,
+}
+
+// Used to tell Tiled JSON files apart from the game's own JSON files
+#[derive(serde::Deserialize)]
+struct JsonKind {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+// Reads a file in the game's own JSON format
+#[allow(non_snake_case)]
+fn read_json_content(path: &path::Path) -> Result<Input, Vec<String>> {
+    let json: JsonContent = read_json(path).map_err(|e| vec![e])?;
+    match json {
+        JsonContent::Map {
+            tileTypes,
+            map,
+            blue,
+            red,
+            green,
+            seed,
+            victory,
+            defeat,
+        } => {
+            let map = json_grid_to_array(map).map_err(|e| vec![file_error(path, e)])?;
+            let tile_types: Vec<_> = tileTypes.into_iter().collect();
+            let name_to_index: collections::HashMap<_, _> = tile_types
+                .iter()
+                .enumerate()
+                .map(|(i, (k, _))| (k.as_str(), i as u32))
+                .collect();
+            let unknown_tiles: Vec<_> = map
+                .indexed_iter()
+                .filter(|(_, name)| !name_to_index.contains_key(name.as_str()))
+                .map(|((row, column), name)| {
+                    let msg = format!("row {}, column {}: unknown tile type {}", row, column, name);
+                    file_error(path, msg)
+                })
+                .collect();
+            if !unknown_tiles.is_empty() {
+                return Err(unknown_tiles);
+            }
+            Ok(Input::Map(MapSource {
+                map: map.map(|x| name_to_index[x.as_str()]),
+                tile_types,
+                blue,
+                red,
+                green,
+                seed,
+                victory,
+                defeat,
+            }))
+        }
+        JsonContent::Campaign { chapters } => Ok(Input::Campaign(chapters)),
+    }
+}
+
+// Reads an input file in any of the supported formats
+fn read_input(path: &path::Path, options: &options::Options) -> Result<Input, Vec<String>> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("tmx") => tiled::read_tmx(path, &options.images).map(Input::Map),
+        Some("tmj") => tiled::read_tmj(path, &options.images).map(Input::Map),
+        _ => {
+            let kind: JsonKind = read_json(path).map_err(|e| vec![e])?;
+            match kind.kind.as_deref() {
+                Some("map") => tiled::read_tmj(path, &options.images).map(Input::Map),
+                Some("tileset") => Ok(Input::Tileset),
+                _ => read_json_content(path),
             }
         }
     }
 }
+
+// Gets the path of a generated file, named after the input file that it was generated from
+fn output_path(
+    path: &path::Path,
+    options: &options::Options,
+    extension: &str,
+) -> Result<path::PathBuf, Vec<String>> {
+    let file_name = path.with_extension(extension);
+    let file_name = file_name
+        .file_name()
+        .ok_or_else(|| vec![file_error(path, "not a file")])?;
+    Ok(options.output.join(file_name))
+}
+
+// Converts an input file into the file that the game reads
+fn convert_file(
+    path: &path::Path,
+    options: &options::Options,
+    languages: &[(String, StringTable)],
+    inputs: &[path::PathBuf],
+) -> Result<(), Vec<String>> {
+    match read_input(path, options)? {
+        Input::Map(source) => convert_map(path, source, options, languages),
+        Input::Campaign(chapters) => convert_campaign(path, &chapters, options, languages, inputs),
+        Input::Tileset => Ok(()),
+    }
+}
+
+// Converts a campaign file into the campaign file for the game
+fn convert_campaign(
+    path: &path::Path,
+    chapters: &[JsonChapter],
+    options: &options::Options,
+    languages: &[(String, StringTable)],
+    inputs: &[path::PathBuf],
+) -> Result<(), Vec<String>> {
+    if chapters.is_empty() {
+        return Err(vec![file_error(path, "the campaign has no chapters")]);
+    }
+
+    // Chapter maps must either be converted along with the campaign or have been converted before
+    let map_exists = |map: &str| {
+        inputs
+            .iter()
+            .any(|i| i != path && i.file_stem().is_some_and(|s| s == map))
+            || options.output.join(format!("{}.map", map)).is_file()
+    };
+    let missing_maps: Vec<_> = chapters
+        .iter()
+        .filter(|c| !map_exists(c.map.as_str()))
+        .map(|c| {
+            file_error(
+                path,
+                format!("chapter {} uses missing map {}", c.name, c.map),
+            )
+        })
+        .collect();
+    if !missing_maps.is_empty() {
+        return Err(missing_maps);
+    }
+    for c in chapters.iter() {
+        for (l, string_map) in languages.iter() {
+            if !string_map.contains_key(&c.name) {
+                println!("{} has no name for chapter {}", l, c.name);
+            }
+        }
+    }
+
+    let campaign = serialization::Campaign {
+        chapters: chapters
+            .iter()
+            .map(|c| serialization::Chapter {
+                map: c.map.as_str(),
+                name: c.name.as_str(),
+            })
+            .collect(),
+    };
+    if !options.check {
+        let out_path = output_path(path, options, "camp")?;
+        let bytes =
+            file_format::encode_campaign(&campaign).map_err(|e| vec![file_error(path, e)])?;
+        std::fs::write(&out_path, bytes).map_err(|e| vec![file_error(&out_path, e)])?;
+    }
+    Ok(())
+}
+
+// Converts a map into a map file for the game
+fn convert_map(
+    path: &path::Path,
+    source: MapSource,
+    options: &options::Options,
+    languages: &[(String, StringTable)],
+) -> Result<(), Vec<String>> {
+    let mut tile_types = vec![];
+    for (k, v) in source.tile_types.iter() {
+        // Tile names are looked up when the game runs, so report any that won't be found
+        for (l, string_map) in languages.iter() {
+            if !string_map.contains_key(k) {
+                println!("{} has no name for tile type {}", l, k);
+            }
+        }
+        tile_types.push(serialization::TileType {
+            name: k.as_str(),
+            image: v.image.as_str(),
+            defense: v.defense,
+            evade: v.evade,
+            move_cost: v.move_cost,
+            heal: v.heal.unwrap_or(hp(0)),
+        });
+    }
+    let new_map = serialization::Map {
+        tile_types,
+        map: source.map,
+        blue: json_units_to_units(&source.blue),
+        red: json_units_to_units(&source.red),
+        green: json_units_to_units(&source.green),
+        seed: source.seed,
+        victory: source
+            .victory
+            .iter()
+            .map(json_objective_to_objective)
+            .collect(),
+        defeat: source.defeat.iter().map(json_defeat_to_defeat).collect(),
+    };
+    let image_exists = |image: &str| options.images.join(image).is_file();
+    let problems: Vec<_> = validation::validate_map(&new_map, image_exists)
+        .iter()
+        .map(|p| file_error(path, p))
+        .collect();
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+    if !options.check {
+        let out_path = output_path(path, options, "map")?;
+        let bytes = file_format::encode_map(&new_map).map_err(|e| vec![file_error(path, e)])?;
+        std::fs::write(&out_path, bytes).map_err(|e| vec![file_error(&out_path, e)])?;
+    }
+    Ok(())
+}
+
+// Finds the map files to convert, expanding directories into the files that they contain
+// Inputs that can't be read are reported as errors
+fn find_inputs(inputs: &[path::PathBuf]) -> (Vec<path::PathBuf>, Vec<String>) {
+    let mut ret = Vec::new();
+    let mut errors = Vec::new();
+    for input in inputs.iter() {
+        if input.is_dir() {
+            let entries = match std::fs::read_dir(input) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    errors.push(file_error(input, e));
+                    continue;
+                }
+            };
+            let mut files: Vec<_> = entries
+                .filter_map(|e| Some(e.ok()?.path()))
+                .filter(|p| {
+                    p.is_file()
+                        && p.extension()
+                            .is_some_and(|e| e == "json" || e == "tmx" || e == "tmj")
+                })
+                .collect();
+            files.sort();
+            ret.append(&mut files);
+        } else if input.is_file() {
+            ret.push(input.clone());
+        } else {
+            errors.push(file_error(input, "no such file or directory"));
+        }
+    }
+    (ret, errors)
+}
+
+fn run(options: &options::Options) -> Result<(), Vec<String>> {
+    let (inputs, mut errors) = find_inputs(&options.inputs);
+    if !options.check {
+        std::fs::create_dir_all(&options.output)
+            .map_err(|e| vec![file_error(&options.output, e)])?;
+    }
+    let languages = compile_languages(options)?;
+    errors.extend(
+        inputs
+            .iter()
+            .filter_map(|path| convert_file(path, options, &languages, &inputs).err())
+            .flatten(),
+    );
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn main() {
+    let options = match options::parse(std::env::args().skip(1)) {
+        Ok(options::Command::Run(options)) => options,
+        Ok(options::Command::Help) => {
+            print!("{}", options::USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, options::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(errors) = run(&options) {
+        for e in errors.iter() {
+            eprintln!("error: {}", e);
+        }
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(dim: [usize; 2], len: usize) -> JsonGrid {
+        JsonGrid {
+            dim,
+            data: (0..len).map(|i| i.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn grids_are_read_in_row_order() {
+        let array = json_grid_to_array(grid([2, 3], 6)).expect("Grid is the right size");
+        assert_eq!(array.dim(), (2, 3));
+        assert_eq!(array[[1, 0]], "3");
+    }
+
+    #[test]
+    fn incomplete_rows_are_reported() {
+        let e = json_grid_to_array(grid([2, 3], 4)).unwrap_err();
+        assert!(e.contains("the last row has 1 of 3 tiles"), "{}", e);
+    }
+
+    #[test]
+    fn wrong_row_counts_are_reported() {
+        let e = json_grid_to_array(grid([2, 3], 9)).unwrap_err();
+        assert!(e.contains("needs 6; that is 3 rows"), "{}", e);
+    }
+
+    #[test]
+    fn empty_grids_with_data_are_reported() {
+        assert!(json_grid_to_array(grid([0, 0], 2)).is_err());
+    }
+}
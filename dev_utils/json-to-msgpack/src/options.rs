@@ -0,0 +1,165 @@
+use std::path;
+
+pub const USAGE: &str = "\
+Usage: json-to-msgpack [OPTIONS] [INPUT...]
+
+Converts map and campaign files into files for the game and compiles the language packs.
+Each INPUT is a JSON map or campaign file, a Tiled map file (.tmx, .tmj, or .json), or a
+directory of them (default: ../../json-files).
+
+Options:
+    -o, --output DIR        Directory to write generated files to (default: ../generated-files)
+    -L, --language-dir DIR  Directory containing language files (default: ../../language)
+    -i, --images DIR        Directory that image paths are relative to (default: ../../public)
+    -l, --language NAME     Only build the named language (may be repeated)
+        --check             Validate the input files without writing anything
+    -h, --help              Print this message
+";
+
+// Settings for a conversion, taken from the command line
+pub struct Options {
+    pub inputs: Vec<path::PathBuf>,
+    pub output: path::PathBuf,
+    pub language_dir: path::PathBuf,
+    pub images: path::PathBuf,
+    pub languages: Vec<String>,
+    pub check: bool,
+}
+
+pub enum Command {
+    Run(Options),
+    Help,
+}
+
+// Parses command line arguments, not including the program name
+pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+    let mut options = Options {
+        inputs: Vec::new(),
+        output: path::PathBuf::from("../generated-files"),
+        language_dir: path::PathBuf::from("../../language"),
+        images: path::PathBuf::from("../../public"),
+        languages: Vec::new(),
+        check: false,
+    };
+    while let Some(arg) = args.next() {
+        // Options that take a value accept both "--option value" and "--option=value"
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => {
+                (name.to_owned(), Some(value.to_owned()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} requires a value", name))
+        };
+        match name.as_str() {
+            "--help" | "--check" if inline_value.is_some() => {
+                return Err(format!("{} does not take a value", name))
+            }
+            "-h" | "--help" => return Ok(Command::Help),
+            "--check" => options.check = true,
+            "-o" | "--output" => options.output = value()?.into(),
+            "-L" | "--language-dir" => options.language_dir = value()?.into(),
+            "-i" | "--images" => options.images = value()?.into(),
+            "-l" | "--language" => {
+                let language = value()?;
+                if !super::LANGUAGES.iter().any(|(l, _, _)| *l == language) {
+                    return Err(format!("unknown language {}", language));
+                }
+                options.languages.push(language);
+            }
+            _ if name.starts_with('-') && name.len() > 1 => {
+                return Err(format!("unknown option {}", name))
+            }
+            _ => options.inputs.push(arg.into()),
+        }
+    }
+    if options.inputs.is_empty() {
+        options.inputs.push(path::PathBuf::from("../../json-files"));
+    }
+    if options.languages.is_empty() {
+        options.languages = super::LANGUAGES
+            .iter()
+            .map(|(l, _, _)| (*l).to_owned())
+            .collect();
+    }
+    // The game falls back to these languages at runtime, so they're always built as well
+    let requested = std::mem::take(&mut options.languages);
+    for l in requested.iter() {
+        for c in super::fallback_chain(l) {
+            if !options.languages.iter().any(|built| built == c) {
+                options.languages.push(c.to_owned());
+            }
+        }
+    }
+    Ok(Command::Run(options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Command, String> {
+        parse(args.iter().map(|a| (*a).to_owned()))
+    }
+
+    fn parse_options(args: &[&str]) -> Options {
+        match parse_args(args) {
+            Ok(Command::Run(options)) => options,
+            Ok(Command::Help) => panic!("{:?} asked for help", args),
+            Err(e) => panic!("{:?} failed to parse: {}", args, e),
+        }
+    }
+
+    #[test]
+    fn defaults_are_used_without_arguments() {
+        let options = parse_options(&[]);
+        assert_eq!(options.inputs, [path::PathBuf::from("../../json-files")]);
+        assert_eq!(options.output, path::PathBuf::from("../generated-files"));
+        assert_eq!(options.languages, ["english", "spanish"]);
+        assert!(!options.check);
+    }
+
+    #[test]
+    fn values_can_be_separate_or_inline() {
+        let options = parse_options(&["-o", "out", "--images=img", "map.json", "--check"]);
+        assert_eq!(options.output, path::PathBuf::from("out"));
+        assert_eq!(options.images, path::PathBuf::from("img"));
+        assert_eq!(options.inputs, [path::PathBuf::from("map.json")]);
+        assert!(options.check);
+    }
+
+    #[test]
+    fn help_is_recognized() {
+        assert!(matches!(parse_args(&["map.json", "-h"]), Ok(Command::Help)));
+        assert!(matches!(parse_args(&["--help"]), Ok(Command::Help)));
+    }
+
+    #[test]
+    fn flags_reject_inline_values() {
+        assert!(parse_args(&["--check=foo"]).is_err());
+        assert!(parse_args(&["--help=foo"]).is_err());
+    }
+
+    #[test]
+    fn bad_arguments_are_rejected() {
+        assert!(parse_args(&["--output"]).is_err());
+        assert!(parse_args(&["--unknown"]).is_err());
+        assert!(parse_args(&["-l", "klingon"]).is_err());
+    }
+
+    #[test]
+    fn languages_are_built_with_their_fallbacks() {
+        assert_eq!(
+            parse_options(&["-l", "spanish"]).languages,
+            ["spanish", "english"]
+        );
+        assert_eq!(
+            parse_options(&["-l", "english", "-l", "spanish"]).languages,
+            ["english", "spanish"]
+        );
+    }
+}
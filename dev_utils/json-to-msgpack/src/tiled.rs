@@ -0,0 +1,794 @@
+// Imports maps made with the Tiled map editor, saved either as TMX or as JSON
+//
+// Tile types come from the tiles in the map's tilesets. Each tile that is used needs move_cost,
+// defense, and evade properties, may have a heal property, and is named by its "name" property or
+// else its class. Tiles use their own image, as in an image collection tileset, unless they have
+// an "image" property.
+//
+// Units come from object layers named blue, red, or green (or with a "faction" property naming
+// one of them). Each object becomes a unit on the tile under its center, named after the object,
+// with its class and stats taken from the object's class and properties.
+//
+// Objectives and defeat conditions are given by the map's victory and defeat properties, which
+// hold the same JSON lists that JSON map files use.
+
+use super::{
+    file_error, read_json, JsonDefeatCondition, JsonObjective, JsonUnit, MapSource, TileTypeInfo,
+};
+use std::{collections, path};
+
+// Bits of a global tile ID that Tiled uses to mark flipped and rotated tiles
+const FLAG_MASK: u32 = 0xf000_0000;
+
+const INFINITE: &str = "infinite maps are not supported";
+
+// Custom properties of a map, layer, tile, or object
+type Properties = serde_json::Map<String, serde_json::Value>;
+
+struct Tile {
+    image: Option<path::PathBuf>,
+    class: Option<String>,
+    properties: Properties,
+}
+
+struct Tileset {
+    first_gid: u32,
+    name: String,
+    tiles: collections::HashMap<u32, Tile>,
+}
+
+struct Object {
+    name: String,
+    class: Option<String>,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    // Tile objects are positioned by their bottom left corner instead of their top left corner
+    is_tile: bool,
+    properties: Properties,
+}
+
+enum Layer {
+    Tiles {
+        name: String,
+        data: Vec<u32>,
+    },
+    Objects {
+        name: String,
+        properties: Properties,
+        objects: Vec<Object>,
+    },
+}
+
+// A map read from either of the formats that Tiled saves maps in
+struct TiledMap {
+    width: usize,
+    height: usize,
+    tile_width: f64,
+    tile_height: f64,
+    properties: Properties,
+    tilesets: Vec<Tileset>,
+    layers: Vec<Layer>,
+}
+
+fn unsupported_data(layer: &str) -> String {
+    format!(
+        "layer {}: only CSV tile layer data is supported; change the tile layer format in the map properties",
+        layer
+    )
+}
+
+// Treats a missing class and an empty one the same way, since Tiled writes both
+fn non_empty(class: Option<String>) -> Option<String> {
+    class.filter(|c| !c.is_empty())
+}
+
+fn parent(path: &path::Path) -> &path::Path {
+    path.parent().unwrap_or_else(|| path::Path::new(""))
+}
+
+// Gets the path of an image relative to the directory that the game loads images from
+fn image_path(image: &path::Path, images: &path::Path) -> Result<String, String> {
+    let full_image = image.canonicalize().map_err(|e| file_error(image, e))?;
+    let images = images.canonicalize().map_err(|e| file_error(images, e))?;
+    let relative = full_image
+        .strip_prefix(&images)
+        .map_err(|_| format!("image {} is not in {}", image.display(), images.display()))?;
+    let parts: Option<Vec<_>> = relative
+        .components()
+        .map(|c| c.as_os_str().to_str())
+        .collect();
+    parts
+        .map(|p| p.join("/"))
+        .ok_or_else(|| format!("image {} does not have a UTF-8 name", image.display()))
+}
+
+// Finds the tile with a global tile ID and gets the type of tile that it represents
+fn tile_type(
+    tilesets: &[Tileset],
+    gid: u32,
+    images: &path::Path,
+) -> Result<(String, TileTypeInfo), String> {
+    let tileset = tilesets
+        .iter()
+        .filter(|t| t.first_gid <= gid)
+        .max_by_key(|t| t.first_gid)
+        .ok_or_else(|| format!("tile {} is not in any tileset", gid))?;
+    let id = gid - tileset.first_gid;
+    let describe = |msg: String| format!("tile {} of tileset {}: {}", id, tileset.name, msg);
+    let tile = tileset
+        .tiles
+        .get(&id)
+        .ok_or_else(|| describe("has no properties".to_owned()))?;
+    let name = match tile.properties.get("name") {
+        Some(serde_json::Value::String(name)) => name.clone(),
+        _ => tile
+            .class
+            .clone()
+            .ok_or_else(|| describe("has no name property or class".to_owned()))?,
+    };
+    let mut info = tile.properties.clone();
+    if !info.contains_key("image") {
+        let image = tile
+            .image
+            .as_ref()
+            .ok_or_else(|| describe("has no image of its own or image property".to_owned()))?;
+        info.insert(
+            "image".to_owned(),
+            image_path(image, images).map_err(describe)?.into(),
+        );
+    }
+    let info = serde_json::from_value(info.into()).map_err(|e| describe(e.to_string()))?;
+    Ok((name, info))
+}
+
+// Reads a list from a map property that holds JSON text
+fn json_list_property<T: serde::de::DeserializeOwned>(
+    properties: &Properties,
+    name: &str,
+) -> Result<Vec<T>, String> {
+    match properties.get(name) {
+        None => Ok(Vec::new()),
+        Some(serde_json::Value::String(text)) => {
+            serde_json::from_str(text).map_err(|e| format!("{} property: {}", name, e))
+        }
+        Some(_) => Err(format!("{} property must be a string", name)),
+    }
+}
+
+// Creates a unit from an object, placing it on the tile under the object's center
+fn object_to_unit(object: &Object, map: &TiledMap) -> Result<JsonUnit, String> {
+    let x = object.x + object.width / 2.0;
+    let y = if object.is_tile {
+        object.y - object.height / 2.0
+    } else {
+        object.y + object.height / 2.0
+    };
+    if x < 0.0 || y < 0.0 {
+        return Err("is not on the map".to_owned());
+    }
+    let mut fields = object.properties.clone();
+    fields.insert("name".to_owned(), object.name.clone().into());
+    if let Some(class) = &object.class {
+        fields.insert("class".to_owned(), class.clone().into());
+    }
+    let position = serde_json::json!({
+        "x": (x / map.tile_width) as u64,
+        "y": (y / map.tile_height) as u64,
+    });
+    fields.insert("position".to_owned(), position);
+    // Units start with all of their movement unless told otherwise
+    if let Some(movement) = fields.get("movement").cloned() {
+        fields.entry("remaining_move").or_insert(movement);
+    }
+    serde_json::from_value(fields.into()).map_err(|e| e.to_string())
+}
+
+// Converts a Tiled map into the tile types, tiles, and units that make up a map for the game
+fn tiled_to_source(
+    path: &path::Path,
+    map: TiledMap,
+    images: &path::Path,
+) -> Result<MapSource, Vec<String>> {
+    let mut errors = Vec::new();
+
+    // Tiles in higher layers cover up the tiles below them
+    let mut gids = vec![0; map.width * map.height];
+    for layer in map.layers.iter() {
+        if let Layer::Tiles { name, data } = layer {
+            if data.len() != gids.len() {
+                let msg = format!(
+                    "layer {} has {} tiles, but the map is {} by {}",
+                    name,
+                    data.len(),
+                    map.width,
+                    map.height
+                );
+                errors.push(file_error(path, msg));
+                continue;
+            }
+            for (gid, tile) in gids.iter_mut().zip(data.iter()) {
+                if tile & !FLAG_MASK != 0 {
+                    *gid = tile & !FLAG_MASK;
+                }
+            }
+        }
+    }
+
+    // Only tiles that are used become tile types, and problems with each are reported once
+    let mut tile_types = Vec::new();
+    let mut gid_to_index = collections::HashMap::new();
+    let mut cells = Vec::with_capacity(gids.len());
+    for (i, gid) in gids.iter().enumerate() {
+        let (row, column) = (i / map.width.max(1), i % map.width.max(1));
+        let index = if *gid == 0 {
+            let msg = format!("row {}, column {}: no tile", row, column);
+            errors.push(file_error(path, msg));
+            None
+        } else {
+            *gid_to_index.entry(*gid).or_insert_with(|| {
+                match tile_type(&map.tilesets, *gid, images) {
+                    Ok(tile_type) => {
+                        tile_types.push(tile_type);
+                        Some(tile_types.len() as u32 - 1)
+                    }
+                    Err(e) => {
+                        let msg = format!("row {}, column {}: {}", row, column, e);
+                        errors.push(file_error(path, msg));
+                        None
+                    }
+                }
+            })
+        };
+        cells.push(index.unwrap_or_default());
+    }
+
+    let mut blue = Vec::new();
+    let mut red = Vec::new();
+    let mut green = Vec::new();
+    for layer in map.layers.iter() {
+        if let Layer::Objects {
+            name,
+            properties,
+            objects,
+        } = layer
+        {
+            let faction = properties
+                .get("faction")
+                .and_then(|f| f.as_str())
+                .unwrap_or(name);
+            let units = match faction.to_lowercase().as_str() {
+                "blue" => &mut blue,
+                "red" => &mut red,
+                "green" => &mut green,
+                _ => {
+                    println!(
+                        "{}: ignoring object layer {}, which is not a faction",
+                        path.display(),
+                        name
+                    );
+                    continue;
+                }
+            };
+            for object in objects.iter() {
+                match object_to_unit(object, &map) {
+                    Ok(unit) => units.push(unit),
+                    Err(e) => {
+                        let msg = format!("object {}: {}", object.name, e);
+                        errors.push(file_error(path, msg));
+                    }
+                }
+            }
+        }
+    }
+
+    let seed = match map.properties.get("seed") {
+        None => 0,
+        Some(seed) => seed.as_u64().unwrap_or_else(|| {
+            errors.push(file_error(path, format!("invalid seed {}", seed)));
+            0
+        }),
+    };
+
+    let victory =
+        json_list_property::<JsonObjective>(&map.properties, "victory").unwrap_or_else(|e| {
+            errors.push(file_error(path, e));
+            Vec::new()
+        });
+    let defeat = json_list_property::<JsonDefeatCondition>(&map.properties, "defeat")
+        .unwrap_or_else(|e| {
+            errors.push(file_error(path, e));
+            Vec::new()
+        });
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let map = ndarray::Array2::from_shape_vec((map.height, map.width), cells)
+        .map_err(|e| vec![file_error(path, e)])?;
+    Ok(MapSource {
+        tile_types,
+        map,
+        blue,
+        red,
+        green,
+        seed,
+        victory,
+        defeat,
+    })
+}
+
+// Reads a tileset that was saved in its own file, in either format
+fn read_tileset_file(path: &path::Path, first_gid: u32) -> Result<Tileset, String> {
+    if path.extension().is_some_and(|e| e == "tsx") {
+        let text = std::fs::read_to_string(path).map_err(|e| file_error(path, e))?;
+        let doc = roxmltree::Document::parse(&text).map_err(|e| file_error(path, e))?;
+        xml_tileset(path, doc.root_element(), first_gid)
+    } else {
+        Ok(json_tileset(parent(path), read_json(path)?, first_gid))
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct JsonProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+fn json_properties(properties: Vec<JsonProperty>) -> Properties {
+    properties.into_iter().map(|p| (p.name, p.value)).collect()
+}
+
+#[derive(serde::Deserialize)]
+struct JsonTile {
+    id: u32,
+    image: Option<String>,
+    #[serde(rename = "type", alias = "class")]
+    class: Option<String>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(serde::Deserialize)]
+struct JsonTileset {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    tiles: Vec<JsonTile>,
+}
+
+// A tileset used by a map, which is either included in the map or saved in another file
+#[derive(serde::Deserialize)]
+struct JsonTilesetRef {
+    firstgid: u32,
+    source: Option<String>,
+    #[serde(flatten)]
+    tileset: JsonTileset,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum JsonData {
+    Tiles(Vec<u32>),
+    // Base64 data, which may also be compressed
+    Encoded(serde::de::IgnoredAny),
+}
+
+#[derive(serde::Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type", alias = "class")]
+    class: Option<String>,
+    x: f64,
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    gid: Option<u32>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum JsonLayer {
+    TileLayer {
+        name: String,
+        data: Option<JsonData>,
+    },
+    ObjectGroup {
+        name: String,
+        #[serde(default)]
+        properties: Vec<JsonProperty>,
+        objects: Vec<JsonObject>,
+    },
+    Group {
+        layers: Vec<JsonLayer>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(serde::Deserialize)]
+struct JsonMap {
+    width: usize,
+    height: usize,
+    tilewidth: f64,
+    tileheight: f64,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+    tilesets: Vec<JsonTilesetRef>,
+    layers: Vec<JsonLayer>,
+}
+
+fn json_tileset(dir: &path::Path, tileset: JsonTileset, first_gid: u32) -> Tileset {
+    let tiles = tileset
+        .tiles
+        .into_iter()
+        .map(|t| {
+            let tile = Tile {
+                image: t.image.map(|i| dir.join(i)),
+                class: non_empty(t.class),
+                properties: json_properties(t.properties),
+            };
+            (t.id, tile)
+        })
+        .collect();
+    Tileset {
+        first_gid,
+        name: tileset.name,
+        tiles,
+    }
+}
+
+// Flattens layers, including those inside of groups, into a list from bottom to top
+fn json_layers(group: Vec<JsonLayer>, layers: &mut Vec<Layer>) -> Result<(), String> {
+    for layer in group {
+        match layer {
+            JsonLayer::TileLayer {
+                name,
+                data: Some(JsonData::Tiles(data)),
+            } => layers.push(Layer::Tiles { name, data }),
+            JsonLayer::TileLayer { name, .. } => return Err(unsupported_data(&name)),
+            JsonLayer::ObjectGroup {
+                name,
+                properties,
+                objects,
+            } => {
+                let objects = objects
+                    .into_iter()
+                    .map(|o| Object {
+                        name: o.name,
+                        class: non_empty(o.class),
+                        x: o.x,
+                        y: o.y,
+                        width: o.width,
+                        height: o.height,
+                        is_tile: o.gid.is_some(),
+                        properties: json_properties(o.properties),
+                    })
+                    .collect();
+                layers.push(Layer::Objects {
+                    name,
+                    properties: json_properties(properties),
+                    objects,
+                });
+            }
+            JsonLayer::Group { layers: inner } => json_layers(inner, layers)?,
+            JsonLayer::Other => {}
+        }
+    }
+    Ok(())
+}
+
+// Reads a map that Tiled saved as JSON
+pub fn read_tmj(path: &path::Path, images: &path::Path) -> Result<MapSource, Vec<String>> {
+    let json: JsonMap = read_json(path).map_err(|e| vec![e])?;
+    if json.infinite {
+        return Err(vec![file_error(path, INFINITE)]);
+    }
+    let mut tilesets = Vec::new();
+    for tileset in json.tilesets {
+        tilesets.push(match tileset.source {
+            Some(source) => read_tileset_file(&parent(path).join(source), tileset.firstgid)
+                .map_err(|e| vec![e])?,
+            None => json_tileset(parent(path), tileset.tileset, tileset.firstgid),
+        });
+    }
+    let mut layers = Vec::new();
+    json_layers(json.layers, &mut layers).map_err(|e| vec![file_error(path, e)])?;
+    let map = TiledMap {
+        width: json.width,
+        height: json.height,
+        tile_width: json.tilewidth,
+        tile_height: json.tileheight,
+        properties: json_properties(json.properties),
+        tilesets,
+        layers,
+    };
+    tiled_to_source(path, map, images)
+}
+
+// Formats an error message about an XML element with the line and column that it starts on
+fn xml_error<E: std::fmt::Display>(path: &path::Path, node: roxmltree::Node, err: E) -> String {
+    let pos = node.document().text_pos_at(node.range().start);
+    format!("{}:{}:{}: {}", path.display(), pos.row, pos.col, err)
+}
+
+fn attribute<T: std::str::FromStr>(
+    path: &path::Path,
+    node: roxmltree::Node,
+    name: &str,
+) -> Result<T, String> {
+    let tag = node.tag_name().name();
+    let value = node
+        .attribute(name)
+        .ok_or_else(|| xml_error(path, node, format!("<{}> has no {} attribute", tag, name)))?;
+    value
+        .parse()
+        .map_err(|_| xml_error(path, node, format!("invalid {} {:?}", name, value)))
+}
+
+fn optional_attribute<T: std::str::FromStr>(
+    path: &path::Path,
+    node: roxmltree::Node,
+    name: &str,
+    default: T,
+) -> Result<T, String> {
+    if node.has_attribute(name) {
+        attribute(path, node, name)
+    } else {
+        Ok(default)
+    }
+}
+
+fn children<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    tag: &'static str,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.children().filter(move |n| n.has_tag_name(tag))
+}
+
+fn xml_class(node: roxmltree::Node) -> Option<String> {
+    let class = node.attribute("class").or_else(|| node.attribute("type"));
+    non_empty(class.map(str::to_owned))
+}
+
+// Gets the custom properties of an element, keeping the types that Tiled gave them
+fn xml_properties(node: roxmltree::Node) -> Properties {
+    let mut properties = Properties::new();
+    for property in children(node, "properties").flat_map(|p| children(p, "property")) {
+        let name = match property.attribute("name") {
+            Some(name) => name,
+            None => continue,
+        };
+        // Multi-line strings are stored as text instead of in the value attribute
+        let value = property
+            .attribute("value")
+            .or_else(|| property.text())
+            .unwrap_or_default();
+        let typed_value = match property.attribute("type") {
+            Some("int") => value.parse::<i64>().ok().map(Into::into),
+            Some("float") => value.parse::<f64>().ok().map(Into::into),
+            Some("bool") => value.parse::<bool>().ok().map(Into::into),
+            _ => None,
+        };
+        properties.insert(name.to_owned(), typed_value.unwrap_or_else(|| value.into()));
+    }
+    properties
+}
+
+fn xml_tileset(
+    path: &path::Path,
+    node: roxmltree::Node,
+    first_gid: u32,
+) -> Result<Tileset, String> {
+    let mut tiles = collections::HashMap::new();
+    for tile in children(node, "tile") {
+        let image = children(tile, "image")
+            .next()
+            .and_then(|i| i.attribute("source"))
+            .map(|source| parent(path).join(source));
+        let new_tile = Tile {
+            image,
+            class: xml_class(tile),
+            properties: xml_properties(tile),
+        };
+        tiles.insert(attribute(path, tile, "id")?, new_tile);
+    }
+    Ok(Tileset {
+        first_gid,
+        name: node.attribute("name").unwrap_or_default().to_owned(),
+        tiles,
+    })
+}
+
+fn xml_tile_data(
+    path: &path::Path,
+    layer: roxmltree::Node,
+    name: &str,
+) -> Result<Vec<u32>, String> {
+    let data = children(layer, "data")
+        .next()
+        .ok_or_else(|| xml_error(path, layer, format!("layer {} has no data", name)))?;
+    match data.attribute("encoding") {
+        Some("csv") => data
+            .text()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(|t| {
+                t.parse()
+                    .map_err(|_| xml_error(path, data, format!("invalid tile {:?}", t)))
+            })
+            .collect(),
+        None => children(data, "tile")
+            .map(|t| optional_attribute(path, t, "gid", 0))
+            .collect(),
+        Some(_) => Err(xml_error(path, data, unsupported_data(name))),
+    }
+}
+
+// Flattens layers, including those inside of groups, into a list from bottom to top
+fn xml_layers(
+    path: &path::Path,
+    node: roxmltree::Node,
+    layers: &mut Vec<Layer>,
+) -> Result<(), String> {
+    for child in node.children() {
+        let name = child.attribute("name").unwrap_or_default().to_owned();
+        match child.tag_name().name() {
+            "layer" => {
+                let data = xml_tile_data(path, child, &name)?;
+                layers.push(Layer::Tiles { name, data });
+            }
+            "objectgroup" => {
+                let mut objects = Vec::new();
+                for object in children(child, "object") {
+                    objects.push(Object {
+                        name: object.attribute("name").unwrap_or_default().to_owned(),
+                        class: xml_class(object),
+                        x: optional_attribute(path, object, "x", 0.0)?,
+                        y: optional_attribute(path, object, "y", 0.0)?,
+                        width: optional_attribute(path, object, "width", 0.0)?,
+                        height: optional_attribute(path, object, "height", 0.0)?,
+                        is_tile: object.has_attribute("gid"),
+                        properties: xml_properties(object),
+                    });
+                }
+                layers.push(Layer::Objects {
+                    name,
+                    properties: xml_properties(child),
+                    objects,
+                });
+            }
+            "group" => xml_layers(path, child, layers)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Reads a map that Tiled saved as TMX
+pub fn read_tmx(path: &path::Path, images: &path::Path) -> Result<MapSource, Vec<String>> {
+    let text = std::fs::read_to_string(path).map_err(|e| vec![file_error(path, e)])?;
+    let doc = roxmltree::Document::parse(&text).map_err(|e| vec![file_error(path, e)])?;
+    let node = doc.root_element();
+    let to_vec = |e| vec![e];
+    if optional_attribute(path, node, "infinite", 0).map_err(to_vec)? != 0 {
+        return Err(vec![file_error(path, INFINITE)]);
+    }
+    let mut tilesets = Vec::new();
+    for tileset in children(node, "tileset") {
+        let first_gid = attribute(path, tileset, "firstgid").map_err(to_vec)?;
+        tilesets.push(
+            match tileset.attribute("source") {
+                Some(source) => read_tileset_file(&parent(path).join(source), first_gid),
+                None => xml_tileset(path, tileset, first_gid),
+            }
+            .map_err(to_vec)?,
+        );
+    }
+    let mut layers = Vec::new();
+    xml_layers(path, node, &mut layers).map_err(to_vec)?;
+    let map = TiledMap {
+        width: attribute(path, node, "width").map_err(to_vec)?,
+        height: attribute(path, node, "height").map_err(to_vec)?,
+        tile_width: attribute(path, node, "tilewidth").map_err(to_vec)?,
+        tile_height: attribute(path, node, "tileheight").map_err(to_vec)?,
+        properties: xml_properties(node),
+        tilesets,
+        layers,
+    };
+    tiled_to_source(path, map, images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Both fixtures hold the same map, stored with an external tileset in TMX and an embedded
+    // tileset and a layer group in JSON
+    fn read(name: &str) -> MapSource {
+        let manifest = path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let path = manifest.join("tests/fixtures/tiled").join(name);
+        let images = manifest.join("../../public");
+        let read = if name.ends_with(".tmx") {
+            read_tmx
+        } else {
+            read_tmj
+        };
+        match read(&path, &images) {
+            Ok(map) => map,
+            Err(errors) => panic!("Failed to read {}: {}", name, errors.join("; ")),
+        }
+    }
+
+    fn for_each_format<F: Fn(MapSource)>(check: F) {
+        check(read("map.tmx"));
+        check(read("map.tmj"));
+    }
+
+    fn position(unit: &JsonUnit) -> (i32, i32) {
+        (unit.position.x.value, unit.position.y.value)
+    }
+
+    #[test]
+    fn tile_types_come_from_the_tileset() {
+        for_each_format(|map| {
+            let names: Vec<_> = map.tile_types.iter().map(|(n, _)| n.as_str()).collect();
+            assert_eq!(names, ["plain", "forest"]);
+            let (_, forest) = &map.tile_types[1];
+            assert_eq!(forest.image, "rough.png");
+            assert_eq!(forest.move_cost.value, 2);
+            assert_eq!(forest.heal.map(|h| h.value), Some(3));
+            assert_eq!(map.tile_types[0].1.heal, None);
+        });
+    }
+
+    #[test]
+    fn higher_layers_cover_lower_ones_ignoring_flip_flags() {
+        for_each_format(|map| {
+            let expected = ndarray::arr2(&[[0, 1, 0], [0, 0, 1]]);
+            assert_eq!(map.map, expected);
+        });
+    }
+
+    #[test]
+    fn tile_objects_are_placed_by_their_bottom_edge() {
+        for_each_format(|map| {
+            assert_eq!(map.blue.len(), 1);
+            assert_eq!(map.blue[0].name, "Maylis");
+            assert_eq!(position(&map.blue[0]), (0, 1));
+            assert_eq!(map.blue[0].remaining_move.value, 3);
+        });
+    }
+
+    #[test]
+    fn object_layers_can_name_their_faction() {
+        for_each_format(|map| {
+            assert_eq!(map.red.len(), 1);
+            assert_eq!(position(&map.red[0]), (2, 0));
+            assert!(map.green.is_empty());
+        });
+    }
+
+    #[test]
+    fn map_properties_hold_the_seed_and_objectives() {
+        for_each_format(|map| {
+            assert_eq!(map.seed, 7);
+            assert!(
+                matches!(&map.victory[..], [JsonObjective::DefeatBoss { unit }] if unit == "Gerard")
+            );
+            assert!(
+                matches!(&map.defeat[..], [JsonDefeatCondition::LordDies { unit }] if unit == "Maylis")
+            );
+        });
+    }
+}
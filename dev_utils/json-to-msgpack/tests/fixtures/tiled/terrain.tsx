@@ -0,0 +1,22 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="terrain" tilewidth="32" tileheight="32" tilecount="2" columns="0">
+ <grid orientation="orthogonal" width="1" height="1"/>
+ <tile id="0" type="plain">
+  <properties>
+   <property name="defense" type="int" value="0"/>
+   <property name="evade" type="int" value="0"/>
+   <property name="move_cost" type="int" value="1"/>
+  </properties>
+  <image width="32" height="32" source="../../../../../public/plain.png"/>
+ </tile>
+ <tile id="1" type="rough">
+  <properties>
+   <property name="defense" type="int" value="1"/>
+   <property name="evade" type="int" value="20"/>
+   <property name="heal" type="int" value="3"/>
+   <property name="move_cost" type="int" value="2"/>
+   <property name="name" value="forest"/>
+  </properties>
+  <image width="32" height="32" source="../../../../../public/rough.png"/>
+ </tile>
+</tileset>